@@ -0,0 +1,63 @@
+//! Compares concatenating the IDAT stream against borrowing its slices
+//! for a large multi-IDAT file. Run with `cargo bench --bench idat`.
+
+use pngyou::{Chunk, ChunkType, Png};
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const IDAT_COUNT: usize = 3200;
+const IDAT_SIZE: usize = 64 * 1024;
+const ITERATIONS: u32 = 10;
+
+fn large_png() -> Png {
+    let idat = ChunkType::from_str("IDAT").unwrap();
+    let mut chunks = vec![Chunk::new(
+        ChunkType::from_str("IHDR").unwrap(),
+        vec![0; 13],
+    )];
+    chunks.extend((0..IDAT_COUNT).map(|i| Chunk::new(idat.clone(), vec![i as u8; IDAT_SIZE])));
+    chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]));
+
+    Png::from_chunks(chunks)
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> u64) {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+
+    println!("{:<24} {:>10.2?} per iteration", name, total / ITERATIONS);
+}
+
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().map(|&b| b as u64).sum()
+}
+
+fn main() {
+    let png = large_png();
+    println!(
+        "{} IDAT chunks of {} KiB ({} MiB total)",
+        IDAT_COUNT,
+        IDAT_SIZE / 1024,
+        IDAT_COUNT * IDAT_SIZE / (1024 * 1024)
+    );
+
+    bench("concatenate", || {
+        let mut buf = Vec::<u8>::new();
+        png.idat_stream_into(&mut buf);
+        checksum(&buf)
+    });
+
+    let mut reused = Vec::<u8>::new();
+    bench("idat_stream_into reused", || {
+        reused.clear();
+        png.idat_stream_into(&mut reused);
+        checksum(&reused)
+    });
+
+    bench("idat_slices", || png.idat_slices().map(checksum).sum());
+}
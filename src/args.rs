@@ -1,5 +1,5 @@
-use anyhow::Error;
-use clap::{Parser, Subcommand};
+use anyhow::{Error, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 use pngyou::ChunkType;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -30,6 +30,35 @@ pub enum Commands {
         /// secret message to be encoded.
         #[arg(short, long)]
         message: String,
+
+        /// re-parse the encoded output and check the message before writing.
+        #[arg(long)]
+        verify: bool,
+
+        /// prefix the message with its length so padding is discarded on decode.
+        #[arg(long)]
+        framed: bool,
+
+        /// prefix the message with a magic tag so decode can recognize it.
+        #[arg(long)]
+        tagged: bool,
+
+        /// compress the message before embedding it (needs the compression feature).
+        #[arg(long)]
+        compress: bool,
+
+        /// encrypt the message with a key derived from this password (needs the encryption feature).
+        /// Prefer setting PNGYOU_PASSWORD, as arguments are visible to other users in the process list.
+        #[arg(long, env = "PNGYOU_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+
+        /// print what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// octal permissions for the output file, such as 644 (unix only).
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<u32>,
     },
 
     /// decode the given file.
@@ -40,6 +69,27 @@ pub enum Commands {
         /// chunk type to decode.
         #[arg(short, long)]
         chunk_type: ChunkType,
+
+        /// text encoding of the message, such as shift_jis (needs the encoding feature).
+        #[arg(long)]
+        encoding: Option<String>,
+
+        /// read the message length from its prefix and discard any padding.
+        #[arg(long)]
+        framed: bool,
+
+        /// only decode messages carrying the magic tag, skipping other chunks.
+        #[arg(long)]
+        tagged: bool,
+
+        /// decrypt the message with a key derived from this password (needs the encryption feature).
+        /// Prefer setting PNGYOU_PASSWORD, as arguments are visible to other users in the process list.
+        #[arg(long, env = "PNGYOU_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+
+        /// how to print messages that are not valid UTF-8.
+        #[arg(long, value_enum, default_value_t = OnInvalid::Hex)]
+        on_invalid: OnInvalid,
     },
 
     /// remove encoded message from the given file.
@@ -54,15 +104,152 @@ pub enum Commands {
         /// chunk type to find and remove.
         #[arg(short, long)]
         chunk_type: ChunkType,
+
+        /// print what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// octal permissions for the output file, such as 644 (unix only).
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<u32>,
+    },
+
+    /// strip hidden data from the given file.
+    Strip {
+        /// path of file to strip.
+        input: InputImage,
+
+        /// path to output stripped file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// remove any bytes after the IEND chunk.
+        #[arg(long)]
+        trailer: bool,
+
+        /// octal permissions for the output file, such as 644 (unix only).
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<u32>,
     },
 
     /// print the file as a raw sequence of bytes.
     Print {
         /// path of file to print.
         input: InputImage,
+
+        /// print the chunks and image dimensions as JSON instead (needs the json feature).
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// list the chunks of the given file, one per line.
+    List {
+        /// path of file to list.
+        input: InputImage,
+    },
+
+    /// print a bar showing how much of the file each chunk takes up.
+    Map {
+        /// path of file to map.
+        input: InputImage,
+
+        /// width of the map in columns.
+        #[arg(short, long, default_value_t = pngyou::Png::ASCII_MAP_WIDTH)]
+        width: usize,
+    },
+
+    /// show the chunks holding the most data.
+    Top {
+        /// path of file to inspect.
+        input: InputImage,
+
+        /// number of chunks to show.
+        #[arg(short = 'n', long, default_value_t = 5)]
+        count: usize,
+    },
+
+    /// watch the given file and decode new messages whenever it changes.
+    #[cfg(feature = "watch")]
+    Watch {
+        /// path of file to watch.
+        input: PathBuf,
+
+        /// chunk type to decode.
+        #[arg(short, long)]
+        chunk_type: ChunkType,
+    },
+
+    /// check the given file against the PNG specification.
+    Verify {
+        /// path of file to verify.
+        input: InputImage,
+    },
+
+    /// report whether the given file was likely produced by this tool.
+    Identify {
+        /// path of file to inspect.
+        input: InputImage,
+    },
+
+    /// list the chunk types registered by the PNG specification.
+    ListTypes,
+
+    /// carve out a PNG embedded inside another file.
+    ExtractPng {
+        /// path of file to search for an embedded PNG.
+        input: InputImage,
+
+        /// path to output carved PNG file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// octal permissions for the output file, such as 644 (unix only).
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<u32>,
+    },
+
+    /// generate a blank cover PNG to embed data in.
+    #[cfg(feature = "image")]
+    Cover {
+        /// path to output cover file.
+        output: PathBuf,
+
+        /// width of the cover in pixels.
+        #[arg(long)]
+        width: u32,
+
+        /// height of the cover in pixels.
+        #[arg(long)]
+        height: u32,
+
+        /// fill the cover with random noise instead of a solid color.
+        #[arg(long)]
+        noise: bool,
+
+        /// solid color of the cover as a hex RGB value, such as ff8800.
+        #[arg(long, default_value = "ffffff", value_parser = parse_color, conflicts_with = "noise")]
+        color: [u8; 3],
+
+        /// octal permissions for the output file, such as 644 (unix only).
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<u32>,
     },
 }
 
+/// How decode prints messages that are not valid UTF-8.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OnInvalid {
+    /// print the bytes as lowercase hex.
+    #[default]
+    Hex,
+    /// print the bytes as standard base64 (needs the base64 feature).
+    Base64,
+    /// replace invalid sequences with U+FFFD.
+    Lossy,
+    /// fail instead of printing the message.
+    Error,
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum InputImage {
     File(PathBuf),
@@ -80,3 +267,23 @@ impl FromStr for InputImage {
         }
     }
 }
+
+fn parse_mode(s: &str) -> Result<u32> {
+    let mode = u32::from_str_radix(s, 8)?;
+    if mode > 0o7777 {
+        anyhow::bail!("Mode {:o} is out of range. Expected at most 7777.", mode);
+    }
+    Ok(mode)
+}
+
+#[cfg(feature = "image")]
+fn parse_color(s: &str) -> Result<[u8; 3]> {
+    let s = s.trim_start_matches('#');
+    if s.len() != 6 {
+        anyhow::bail!("Expected 6 hex digits, found '{}'.", s);
+    }
+
+    let value = u32::from_str_radix(s, 16)?;
+    let [_, r, g, b] = value.to_be_bytes();
+    Ok([r, g, b])
+}
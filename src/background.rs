@@ -0,0 +1,38 @@
+/// The [Background] enum represents the default background color
+/// stored in a bKGD chunk, whose layout depends on the color type of the image.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Background {
+    /// A palette index, for indexed-color images.
+    PaletteIndex(u8),
+    /// A gray level, for grayscale images with or without alpha.
+    Gray(u16),
+    /// Red, green and blue levels, for truecolor images with or without alpha.
+    Rgb(u16, u16, u16),
+}
+
+impl Background {
+    /// Returns the bytes of the bKGD chunk data.
+    pub fn to_bytes(self) -> Vec<u8> {
+        match self {
+            Self::PaletteIndex(index) => vec![index],
+            Self::Gray(level) => level.to_be_bytes().to_vec(),
+            Self::Rgb(red, green, blue) => [red, green, blue]
+                .iter()
+                .flat_map(|level| level.to_be_bytes())
+                .collect(),
+        }
+    }
+
+    /// Reads a background color from bKGD chunk data, telling the layouts apart by length.
+    /// Returns `None` if the data is not 1, 2 or 6 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Background> {
+        let level = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+
+        match bytes.len() {
+            1 => Some(Self::PaletteIndex(bytes[0])),
+            2 => Some(Self::Gray(level(0))),
+            6 => Some(Self::Rgb(level(0), level(2), level(4))),
+            _ => None,
+        }
+    }
+}
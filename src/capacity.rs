@@ -0,0 +1,46 @@
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// The [CapacityReport] struct summarizes how many payload bytes
+/// a PNG can hide using each embedding mode.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct CapacityReport {
+    /// Maximum payload of a single custom chunk, as limited by the PNG specification.
+    pub chunk: usize,
+
+    /// Maximum payload of the trailer after IEND.
+    /// [None] means it is practically unbounded.
+    pub trailer: Option<usize>,
+
+    /// LSB capacity when hiding 1, 2 and 3 bits in every sample, as accepted by
+    /// [crate::stego::embed_lsb_with] with the matching
+    /// [bits_per_channel](crate::stego::LsbOptions::bits_per_channel).
+    pub lsb: [usize; 3],
+}
+
+impl CapacityReport {
+    /// Maximum length of a chunk's data, per the PNG specification.
+    pub const MAX_CHUNK_LENGTH: usize = (1 << 31) - 1;
+
+    /// Returns the LSB capacity when hiding the given number of bits in every sample,
+    /// or [None] if it was not computed.
+    pub fn lsb_bits(&self, bits_per_channel: usize) -> Option<usize> {
+        bits_per_channel
+            .checked_sub(1)
+            .and_then(|i| self.lsb.get(i).copied())
+    }
+}
+
+impl Display for CapacityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "Custom chunk: {} bytes", self.chunk)?;
+        match self.trailer {
+            Some(trailer) => writeln!(f, "Trailer: {} bytes", trailer)?,
+            None => writeln!(f, "Trailer: unbounded")?,
+        }
+
+        for (i, lsb) in self.lsb.iter().enumerate() {
+            writeln!(f, "LSB ({} bits per channel): {} bytes", i + 1, lsb)?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
+use super::background::Background;
 use super::chunk_type::ChunkType;
-use anyhow::{Error, Result, bail};
+use super::error::PngError;
+use super::time::TimeFields;
+use anyhow::{Result, anyhow, bail};
 use crc::{CRC_32_ISO_HDLC, Crc};
 use std::fmt::{Display, Error as FmtError, Formatter};
 
@@ -14,53 +17,21 @@ pub struct Chunk {
 }
 
 impl TryFrom<&[u8]> for Chunk {
-    type Error = Error;
+    type Error = PngError;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        if bytes.len() < 12 {
-            bail!("Invalid chunk. Minimum chunk size must be of 12 bytes.");
-        }
-
-        let mut length = [0u8; 4];
-        let mut chunk_type_bytes = [0u8; 4];
-        let mut data = Vec::<u8>::with_capacity(bytes.len() - 12);
-
-        length.copy_from_slice(&bytes[0..4]);
-        chunk_type_bytes.copy_from_slice(&bytes[4..8]);
-        data.extend_from_slice(&bytes[8..(bytes.len() - 4)]);
-
-        let expected_length = u32::from_be_bytes(length) as usize;
-        if data.len() != expected_length {
-            bail!(
-                "Mismatched length: header says {}, but found {} bytes",
-                expected_length,
-                data.len()
-            );
-        }
-
-        let crc_handler = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-
-        let mut crc_input = Vec::with_capacity(4 + data.len());
-        crc_input.extend_from_slice(&chunk_type_bytes);
-        crc_input.extend_from_slice(&data);
-
-        let crc_expected = crc_handler.checksum(&crc_input).to_be_bytes();
-
-        let mut crc = [0u8; 4];
-        crc.copy_from_slice(&bytes[(bytes.len() - 4)..bytes.len()]);
-
-        if crc != crc_expected {
-            bail!("CRC mismatched!");
+        let chunk = Self::from_bytes_unverified(bytes)?;
+        ChunkType::try_from(chunk.chunk_type.bytes())?;
+
+        let expected = u32::from_be_bytes(Self::compute_crc(&chunk.chunk_type, &chunk.data));
+        if chunk.crc() != expected {
+            return Err(PngError::CrcMismatch {
+                expected,
+                found: chunk.crc(),
+            });
         }
 
-        let chunk_type = ChunkType::try_from(chunk_type_bytes)?;
-
-        Ok(Self {
-            length,
-            chunk_type,
-            data,
-            crc,
-        })
+        Ok(chunk)
     }
 }
 
@@ -85,13 +56,7 @@ impl Chunk {
         u32::try_from(length)
             .unwrap_or_else(|_| panic!("Chunk data is too big! Max size is: {} bytes", u32::MAX));
 
-        let mut bytes = Vec::with_capacity(4 + length);
-
-        bytes.extend_from_slice(&chunk_type.bytes());
-        bytes.extend_from_slice(&data);
-
-        let crc_handler = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-        let crc = crc_handler.checksum(&bytes).to_be_bytes();
+        let crc = Self::compute_crc(&chunk_type, &data);
 
         Self {
             length: (length as u32).to_be_bytes(),
@@ -101,6 +66,87 @@ impl Chunk {
         }
     }
 
+    /// Creates a new [Chunk] instance from raw bytes without verifying its CRC.
+    ///
+    /// The stored CRC is kept as-is, so a wrong CRC is written back
+    /// unchanged by [Chunk::as_bytes] until [Chunk::repair_crc] is called.
+    ///
+    /// The chunk type is not checked either, so a chunk such as `I4AT` still loads
+    /// here while [Chunk::try_from] rejects it.
+    /// [Png::validate](crate::Png::validate) reports chunk types that are not four ASCII letters.
+    ///
+    /// # Error
+    /// Returns an error if the bytes are too short or
+    /// the declared length does not match the data.
+    pub fn from_bytes_unverified(bytes: &[u8]) -> Result<Chunk, PngError> {
+        if bytes.len() < 12 {
+            return Err(PngError::TruncatedInput {
+                expected: 12,
+                found: bytes.len(),
+            });
+        }
+
+        let mut length = [0u8; 4];
+        let mut chunk_type_bytes = [0u8; 4];
+        let mut data = Vec::<u8>::with_capacity(bytes.len() - 12);
+
+        length.copy_from_slice(&bytes[0..4]);
+        chunk_type_bytes.copy_from_slice(&bytes[4..8]);
+        data.extend_from_slice(&bytes[8..(bytes.len() - 4)]);
+
+        let expected_length = u32::from_be_bytes(length) as usize;
+        if data.len() != expected_length {
+            return Err(PngError::LengthMismatch {
+                declared: expected_length,
+                found: data.len(),
+            });
+        }
+
+        let mut crc = [0u8; 4];
+        crc.copy_from_slice(&bytes[(bytes.len() - 4)..bytes.len()]);
+
+        let chunk_type = ChunkType::from_bytes_unchecked(chunk_type_bytes);
+
+        Ok(Self {
+            length,
+            chunk_type,
+            data,
+            crc,
+        })
+    }
+
+    /// Replaces the data of the chunk, recomputing its length and CRC.
+    ///
+    /// # Panics
+    /// Panics if the data is longer than [u32::MAX] bytes, like [Chunk::new].
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        let length = u32::try_from(data.len())
+            .unwrap_or_else(|_| panic!("Chunk data is too big! Max size is: {} bytes", u32::MAX));
+
+        self.length = length.to_be_bytes();
+        self.crc = Self::compute_crc(&self.chunk_type, &data);
+        self.data = data;
+    }
+
+    /// Checks if the stored CRC matches the chunk type and data.
+    pub fn has_valid_crc(&self) -> bool {
+        self.crc == Self::compute_crc(&self.chunk_type, &self.data)
+    }
+
+    /// Recomputes the stored CRC from the chunk type and data.
+    pub fn repair_crc(&mut self) {
+        self.crc = Self::compute_crc(&self.chunk_type, &self.data);
+    }
+
+    pub(crate) fn compute_crc(chunk_type: &ChunkType, data: &[u8]) -> [u8; 4] {
+        let crc_handler = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+        let mut digest = crc_handler.digest();
+
+        digest.update(&chunk_type.bytes());
+        digest.update(data);
+        digest.finalize().to_be_bytes()
+    }
+
     /// Returns the length of the chunk.
     pub fn length(&self) -> u32 {
         u32::from_be_bytes(self.length)
@@ -126,6 +172,217 @@ impl Chunk {
         Ok(str::from_utf8(&self.data)?.to_string())
     }
 
+    /// Returns the data of the chunk decoded with the text encoding of the given label,
+    /// such as "shift_jis" or "windows-1252".
+    ///
+    /// # Error
+    /// Returns an error if the label is not a known encoding
+    /// or the data is malformed in that encoding.
+    #[cfg(feature = "encoding")]
+    pub fn data_as_encoded(&self, label: &str) -> Result<String> {
+        let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| anyhow!("Unknown text encoding: {}", label))?;
+
+        encoding
+            .decode_without_bom_handling_and_without_replacement(&self.data)
+            .map(|text| text.into_owned())
+            .ok_or_else(|| anyhow!("Chunk data is not valid {}.", encoding.name()))
+    }
+
+    /// Creates a new tEXt [Chunk] holding a keyword and its text.
+    ///
+    /// # Error
+    /// Returns an error if the keyword is not 1 to 79 characters long,
+    /// contains a null character or either string is not Latin-1.
+    pub fn new_text(keyword: &str, text: &str) -> Result<Chunk> {
+        if keyword.is_empty() || keyword.chars().count() > 79 {
+            bail!("Keyword must be between 1 and 79 characters long.");
+        }
+
+        if keyword.contains('\0') {
+            bail!("Keyword must not contain a null character.");
+        }
+
+        let mut data = Self::encode_latin1(keyword)?;
+        data.push(0);
+        data.extend(Self::encode_latin1(text)?);
+
+        Ok(Self::new(ChunkType::TEXT, data))
+    }
+
+    /// Returns the keyword and text of a tEXt chunk.
+    ///
+    /// # Error
+    /// Returns an error if the chunk is not a tEXt chunk
+    /// or its keyword is not null-terminated.
+    pub fn as_text(&self) -> Result<(String, String)> {
+        if &self.chunk_type.bytes() != b"tEXt" {
+            bail!("Expected tEXt chunk, found '{}'.", self.chunk_type);
+        }
+
+        let separator = match self.data.iter().position(|b| *b == 0) {
+            Some(i) => i,
+            None => bail!("Missing null separator after keyword."),
+        };
+
+        let decode_latin1 = |bytes: &[u8]| bytes.iter().map(|b| *b as char).collect::<String>();
+
+        Ok((
+            decode_latin1(&self.data[..separator]),
+            decode_latin1(&self.data[(separator + 1)..]),
+        ))
+    }
+
+    /// Creates a new tIME [Chunk] holding a UTC last-modification time.
+    ///
+    /// # Error
+    /// Returns an error if any field is out of range.
+    pub fn new_time(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Chunk> {
+        let time = TimeFields {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        };
+        time.validate()?;
+
+        let mut data = Vec::<u8>::with_capacity(7);
+        data.extend_from_slice(&year.to_be_bytes());
+        data.extend_from_slice(&[month, day, hour, minute, second]);
+
+        Ok(Self::new(ChunkType::TIME, data))
+    }
+
+    /// Returns the last-modification time stored in a tIME chunk.
+    ///
+    /// # Error
+    /// Returns an error if the chunk is not a tIME chunk,
+    /// its data is not 7 bytes long or any field is out of range.
+    pub fn as_time(&self) -> Result<TimeFields> {
+        if &self.chunk_type.bytes() != b"tIME" {
+            bail!("Expected tIME chunk, found '{}'.", self.chunk_type);
+        }
+
+        if self.data.len() != 7 {
+            bail!(
+                "Invalid tIME chunk. Expected 7 bytes of data, found {}.",
+                self.data.len()
+            );
+        }
+
+        let time = TimeFields {
+            year: u16::from_be_bytes([self.data[0], self.data[1]]),
+            month: self.data[2],
+            day: self.data[3],
+            hour: self.data[4],
+            minute: self.data[5],
+            second: self.data[6],
+        };
+        time.validate()?;
+
+        Ok(time)
+    }
+
+    /// Creates a new IHDR [Chunk] for a non-interlaced image with the given format,
+    /// using the only compression and filter methods defined by the PNG specification.
+    ///
+    /// The values are not checked, see [Png::validate](crate::Png::validate).
+    pub fn new_ihdr(width: u32, height: u32, bit_depth: u8, color_type: u8) -> Chunk {
+        let mut data = Vec::<u8>::with_capacity(13);
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+        Self::new(ChunkType::IHDR, data)
+    }
+
+    /// Creates a new pHYs [Chunk] holding the pixels per unit along each axis.
+    /// The unit is 0 when only the aspect ratio is known, or 1 for meters.
+    ///
+    /// # Error
+    /// Returns an error if the unit is neither 0 nor 1.
+    pub fn new_phys(x_ppu: u32, y_ppu: u32, unit: u8) -> Result<Chunk> {
+        if unit > 1 {
+            bail!("Invalid pHYs unit: {}. Must be 0 or 1.", unit);
+        }
+
+        let mut data = Vec::<u8>::with_capacity(9);
+        data.extend_from_slice(&x_ppu.to_be_bytes());
+        data.extend_from_slice(&y_ppu.to_be_bytes());
+        data.push(unit);
+
+        Ok(Self::new(ChunkType::PHYS, data))
+    }
+
+    /// Returns the pixels per unit along each axis and the unit stored in a pHYs chunk.
+    ///
+    /// # Error
+    /// Returns an error if the chunk is not a pHYs chunk,
+    /// its data is not 9 bytes long or the unit is neither 0 nor 1.
+    pub fn as_phys(&self) -> Result<(u32, u32, u8)> {
+        if &self.chunk_type.bytes() != b"pHYs" {
+            bail!("Expected pHYs chunk, found '{}'.", self.chunk_type);
+        }
+
+        if self.data.len() != 9 {
+            bail!(
+                "Invalid pHYs chunk. Expected 9 bytes of data, found {}.",
+                self.data.len()
+            );
+        }
+
+        let unit = self.data[8];
+        if unit > 1 {
+            bail!("Invalid pHYs unit: {}. Must be 0 or 1.", unit);
+        }
+
+        Ok((
+            u32::from_be_bytes([self.data[0], self.data[1], self.data[2], self.data[3]]),
+            u32::from_be_bytes([self.data[4], self.data[5], self.data[6], self.data[7]]),
+            unit,
+        ))
+    }
+
+    /// Creates a new bKGD [Chunk] holding the default background color.
+    ///
+    /// The variant must match the color type of the image the chunk is added to.
+    pub fn new_bkgd(background: Background) -> Chunk {
+        Self::new(ChunkType::BKGD, background.to_bytes())
+    }
+
+    /// Returns the default background color stored in a bKGD chunk.
+    ///
+    /// # Error
+    /// Returns an error if the chunk is not a bKGD chunk
+    /// or its data is not 1, 2 or 6 bytes long.
+    pub fn as_bkgd(&self) -> Result<Background> {
+        if &self.chunk_type.bytes() != b"bKGD" {
+            bail!("Expected bKGD chunk, found '{}'.", self.chunk_type);
+        }
+
+        Background::from_bytes(&self.data).ok_or_else(|| {
+            anyhow!(
+                "Invalid bKGD chunk. Expected 1, 2 or 6 bytes of data, found {}.",
+                self.data.len()
+            )
+        })
+    }
+
+    fn encode_latin1(s: &str) -> Result<Vec<u8>> {
+        s.chars()
+            .map(|c| u8::try_from(c).map_err(|_| anyhow!("'{}' is not a Latin-1 character.", c)))
+            .collect()
+    }
+
     /// Returns the chunk as a list of bytes.
     pub fn as_bytes(&self) -> Vec<u8> {
         let data_len = self.data.len();
@@ -195,6 +452,21 @@ mod tests {
         assert_eq!(chunk_string, expected_chunk_string);
     }
 
+    #[test]
+    fn test_set_data() {
+        let mut chunk = testing_chunk();
+        chunk.set_data(b"This is where your secret message will be!".to_vec());
+        assert_eq!(chunk, testing_chunk());
+
+        chunk.set_data(b"A shorter message".to_vec());
+        let expected = Chunk::new(ChunkType::from_str("RuSt").unwrap(), chunk.data().to_vec());
+
+        assert_eq!(chunk.length(), 17);
+        assert_eq!(chunk.crc(), expected.crc());
+        assert_ne!(chunk.crc(), 2882656334);
+        assert!(chunk.has_valid_crc());
+    }
+
     #[test]
     fn test_chunk_crc() {
         let chunk = testing_chunk();
@@ -246,7 +518,209 @@ mod tests {
 
         let chunk = Chunk::try_from(chunk_data.as_ref());
 
-        assert!(chunk.is_err());
+        assert_eq!(
+            chunk.unwrap_err(),
+            PngError::CrcMismatch {
+                expected: 2882656334,
+                found: 2882656333
+            }
+        );
+    }
+
+    #[test]
+    fn test_chunk_length_mismatch() {
+        let mut chunk_data = testing_chunk().as_bytes();
+        chunk_data[3] = 43;
+
+        assert_eq!(
+            Chunk::try_from(chunk_data.as_ref()).unwrap_err(),
+            PngError::LengthMismatch {
+                declared: 43,
+                found: 42
+            }
+        );
+        assert_eq!(
+            Chunk::try_from(&chunk_data[..8]).unwrap_err(),
+            PngError::TruncatedInput {
+                expected: 12,
+                found: 8
+            }
+        );
+    }
+
+    #[test]
+    fn test_chunk_from_bytes_unverified() {
+        let data_length: u32 = 42;
+        let chunk_type = "RuSt".as_bytes();
+        let message_bytes = "This is where your secret message will be!".as_bytes();
+        let crc: u32 = 2882656333;
+
+        let chunk_data: Vec<u8> = data_length
+            .to_be_bytes()
+            .iter()
+            .chain(chunk_type.iter())
+            .chain(message_bytes.iter())
+            .chain(crc.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        let mut chunk = Chunk::from_bytes_unverified(chunk_data.as_ref()).unwrap();
+        assert!(!chunk.has_valid_crc());
+        assert_eq!(chunk.crc(), 2882656333);
+        assert_eq!(chunk.as_bytes(), chunk_data);
+
+        chunk.repair_crc();
+        assert!(chunk.has_valid_crc());
+        assert_eq!(chunk.crc(), 2882656334);
+    }
+
+    #[test]
+    fn test_chunk_from_bytes_non_letter_type() {
+        let chunk_type = ChunkType::from_bytes_unchecked(*b"I4AT");
+        let bytes = Chunk::new(chunk_type.clone(), b"data".to_vec()).as_bytes();
+
+        assert_eq!(
+            Chunk::try_from(bytes.as_ref()),
+            Err(PngError::InvalidChunkType {
+                chunk_type: b"I4AT".to_vec()
+            })
+        );
+
+        let chunk = Chunk::from_bytes_unverified(bytes.as_ref()).unwrap();
+        assert_eq!(chunk.chunk_type(), &chunk_type);
+        assert!(!chunk.chunk_type().is_valid());
+    }
+
+    #[test]
+    fn test_text_chunk_round_trip() {
+        let chunk = Chunk::new_text("Comment", "Caf\u{e9} secret").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "tEXt");
+        assert_eq!(chunk.data(), b"Comment\0Caf\xe9 secret");
+
+        let (keyword, text) = chunk.as_text().unwrap();
+        assert_eq!(keyword, "Comment");
+        assert_eq!(text, "Caf\u{e9} secret");
+    }
+
+    #[test]
+    fn test_invalid_text_chunk() {
+        assert!(Chunk::new_text("", "text").is_err());
+        assert!(Chunk::new_text(&"k".repeat(80), "text").is_err());
+        assert!(Chunk::new_text("Comment", "\u{1F980}").is_err());
+
+        let chunk = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"no separator".to_vec(),
+        );
+        assert!(chunk.as_text().is_err());
+
+        assert!(testing_chunk().as_text().is_err());
+    }
+
+    #[test]
+    fn test_time_chunk_round_trip() {
+        let chunk = Chunk::new_time(2024, 2, 29, 13, 37, 42).unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "tIME");
+        assert_eq!(chunk.data(), &[7, 232, 2, 29, 13, 37, 42]);
+
+        let time = chunk.as_time().unwrap();
+        assert_eq!(
+            time,
+            TimeFields {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 13,
+                minute: 37,
+                second: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_time_chunk() {
+        assert!(Chunk::new_time(2024, 13, 1, 0, 0, 0).is_err());
+        assert!(Chunk::new_time(2024, 1, 1, 24, 0, 0).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("tIME").unwrap(), vec![7, 232, 2]);
+        assert!(chunk.as_time().is_err());
+
+        assert!(testing_chunk().as_time().is_err());
+    }
+
+    #[test]
+    fn test_ihdr_chunk() {
+        let chunk = Chunk::new_ihdr(640, 480, 8, 6);
+        let png = crate::Png::from_chunks(vec![chunk.clone()]);
+
+        assert_eq!(chunk.chunk_type(), &ChunkType::IHDR);
+        assert_eq!(chunk.length(), 13);
+        assert_eq!(png.dimensions().unwrap(), (640, 480));
+        assert_eq!(&chunk.data()[8..], [8, 6, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_phys_chunk_round_trip() {
+        let chunk = Chunk::new_phys(2835, 2835, 1).unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "pHYs");
+        assert_eq!(chunk.data(), &[0, 0, 11, 19, 0, 0, 11, 19, 1]);
+        assert_eq!(chunk.as_phys().unwrap(), (2835, 2835, 1));
+
+        let chunk = Chunk::new_phys(1, 2, 0).unwrap();
+        assert_eq!(chunk.as_phys().unwrap(), (1, 2, 0));
+    }
+
+    #[test]
+    fn test_invalid_phys_chunk() {
+        assert!(Chunk::new_phys(1, 1, 2).is_err());
+
+        let chunk_type = ChunkType::from_str("pHYs").unwrap();
+        assert!(
+            Chunk::new(chunk_type.clone(), vec![0; 8])
+                .as_phys()
+                .is_err()
+        );
+        assert!(
+            Chunk::new(chunk_type, vec![0, 0, 0, 1, 0, 0, 0, 1, 7])
+                .as_phys()
+                .is_err()
+        );
+        assert!(testing_chunk().as_phys().is_err());
+    }
+
+    #[test]
+    fn test_bkgd_chunk_round_trip() {
+        for (background, data) in [
+            (Background::PaletteIndex(3), vec![3]),
+            (Background::Gray(0x1234), vec![0x12, 0x34]),
+            (
+                Background::Rgb(0xFFFF, 0x8000, 0),
+                vec![0xFF, 0xFF, 0x80, 0, 0, 0],
+            ),
+        ] {
+            let chunk = Chunk::new_bkgd(background);
+            assert_eq!(&chunk.chunk_type().to_string(), "bKGD");
+            assert_eq!(chunk.data(), data);
+            assert_eq!(chunk.as_bkgd().unwrap(), background);
+        }
+    }
+
+    #[test]
+    fn test_invalid_bkgd_chunk() {
+        let chunk = Chunk::new(ChunkType::from_str("bKGD").unwrap(), vec![0; 4]);
+        assert!(chunk.as_bkgd().is_err());
+        assert!(testing_chunk().as_bkgd().is_err());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_data_as_encoded() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = Chunk::new(chunk_type, vec![0x93, 0xFA, 0x96, 0x7B]);
+
+        assert_eq!(chunk.data_as_encoded("shift_jis").unwrap(), "日本");
+        assert!(chunk.data_as_encoded("not-an-encoding").is_err());
+        assert!(testing_chunk().data_as_encoded("windows-1252").is_ok());
     }
 
     #[test]
@@ -1,48 +1,75 @@
-use anyhow::{Error, bail};
+use super::error::PngError;
 use std::fmt::{Display, Error as FmtError, Formatter};
 use std::str::FromStr;
 
 /// The [ChunkType] struct represents the type of a particular chunk.
+///
+/// The case of each of its four letters encodes a property of the chunk:
+///
+/// ```
+/// use pngyou::ChunkType;
+/// use std::str::FromStr;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let chunk_type = ChunkType::from_str("RuSt")?;
+///
+/// assert!(chunk_type.is_critical());
+/// assert!(!chunk_type.is_public());
+/// assert!(chunk_type.is_reserved_bit_valid());
+/// assert!(chunk_type.is_safe_to_copy());
+/// assert!(chunk_type.is_valid());
+/// assert_eq!(&chunk_type.bytes(), b"RuSt");
+/// # Ok(())
+/// # }
+/// ```
 #[derive(PartialEq, Clone, Eq, Debug)]
 pub struct ChunkType {
     bytes: [u8; 4],
 }
 
+/// The property flags encoded in the case of each letter of a chunk type.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ChunkProperties {
+    /// The chunk is required to display the image.
+    pub critical: bool,
+    /// The chunk type is defined by the PNG specification or a registered extension.
+    pub public: bool,
+    /// The reserved bit is unset, as required by the current specification.
+    pub reserved_valid: bool,
+    /// The chunk may be copied by editors that do not recognize it.
+    pub safe_to_copy: bool,
+}
+
 impl TryFrom<[u8; 4]> for ChunkType {
-    type Error = Error;
+    type Error = PngError;
 
+    /// Creates a chunk type from its bytes, which must all be ASCII letters.
+    /// The reserved bit is not checked, see [ChunkType::is_valid].
     fn try_from(bytes: [u8; 4]) -> Result<Self, Self::Error> {
-        let mut chunk_type = Self { bytes: [0; 4] };
-
-        bytes.iter().enumerate().for_each(|(i, b)| {
-            chunk_type.bytes[i] = *b;
-        });
+        if !bytes.iter().all(u8::is_ascii_alphabetic) {
+            return Err(PngError::InvalidChunkType {
+                chunk_type: bytes.to_vec(),
+            });
+        }
 
-        Ok(chunk_type)
+        Ok(Self { bytes })
     }
 }
 
 impl FromStr for ChunkType {
-    type Err = Error;
+    type Err = PngError;
 
+    /// Creates a chunk type from a string of four ASCII letters,
+    /// applying the same checks as [ChunkType::try_from].
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 4 {
-            bail!("Invalid length of byte.");
-        }
-
-        let mut chunk_type = Self { bytes: [0; 4] };
-
-        if !s.bytes().enumerate().all(|(i, b)| {
-            if !b.is_ascii_alphabetic() {
-                return false;
-            }
-            chunk_type.bytes[i] = b;
-            true
-        }) {
-            bail!("Not a valid ascii alphabetic character string.");
-        }
-
-        Ok(chunk_type)
+        let bytes: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| PngError::InvalidChunkType {
+                chunk_type: s.as_bytes().to_vec(),
+            })?;
+
+        Self::try_from(bytes)
     }
 }
 
@@ -56,11 +83,98 @@ impl Display for ChunkType {
 }
 
 impl ChunkType {
+    /// The image header, which must be the first chunk.
+    pub const IHDR: ChunkType = ChunkType { bytes: *b"IHDR" };
+
+    /// The palette, required for indexed-color images.
+    pub const PLTE: ChunkType = ChunkType { bytes: *b"PLTE" };
+
+    /// The compressed image data, possibly split across several consecutive chunks.
+    pub const IDAT: ChunkType = ChunkType { bytes: *b"IDAT" };
+
+    /// The image trailer, which must be the last chunk.
+    pub const IEND: ChunkType = ChunkType { bytes: *b"IEND" };
+
+    /// Textual data, built by [Chunk::new_text](crate::Chunk::new_text).
+    pub(crate) const TEXT: ChunkType = ChunkType { bytes: *b"tEXt" };
+
+    /// The last modification time, built by [Chunk::new_time](crate::Chunk::new_time).
+    pub(crate) const TIME: ChunkType = ChunkType { bytes: *b"tIME" };
+
+    /// The physical pixel dimensions, built by [Chunk::new_phys](crate::Chunk::new_phys).
+    pub(crate) const PHYS: ChunkType = ChunkType { bytes: *b"pHYs" };
+
+    /// The background color, built by [Chunk::new_bkgd](crate::Chunk::new_bkgd).
+    pub(crate) const BKGD: ChunkType = ChunkType { bytes: *b"bKGD" };
+
+    /// The chunk types the PNG specification defines as critical. Methods that
+    /// would break the image, such as removing or renaming chunks, refuse to touch them.
+    pub const STANDARD_CRITICAL: [ChunkType; 4] = [Self::IHDR, Self::PLTE, Self::IDAT, Self::IEND];
+
+    /// Ancillary chunk types that change how the pixels are displayed:
+    /// transparency, significant bits and color space information.
+    pub(crate) const RENDERING: [ChunkType; 7] = [
+        ChunkType { bytes: *b"tRNS" },
+        ChunkType { bytes: *b"gAMA" },
+        ChunkType { bytes: *b"cHRM" },
+        ChunkType { bytes: *b"sRGB" },
+        ChunkType { bytes: *b"iCCP" },
+        ChunkType { bytes: *b"cICP" },
+        ChunkType { bytes: *b"sBIT" },
+    ];
+
+    /// Chunk types registered by the PNG specification, along with a short description of each.
+    pub const REGISTERED: &'static [(&'static str, &'static str)] = &[
+        ("IHDR", "image header"),
+        ("PLTE", "palette"),
+        ("IDAT", "image data"),
+        ("IEND", "image trailer"),
+        ("acTL", "animation control"),
+        ("bKGD", "background color"),
+        ("cHRM", "primary chromaticities and white point"),
+        ("cICP", "coding-independent code points"),
+        ("cLLI", "content light level information"),
+        ("eXIf", "exif metadata"),
+        ("fcTL", "frame control"),
+        ("fdAT", "frame data"),
+        ("gAMA", "image gamma"),
+        ("hIST", "palette histogram"),
+        ("iCCP", "embedded ICC profile"),
+        ("iTXt", "international textual data"),
+        ("mDCV", "mastering display color volume"),
+        ("pHYs", "physical pixel dimensions"),
+        ("sBIT", "significant bits"),
+        ("sPLT", "suggested palette"),
+        ("sRGB", "standard RGB color space"),
+        ("tEXt", "textual data"),
+        ("tIME", "image last-modification time"),
+        ("tRNS", "transparency"),
+        ("zTXt", "compressed textual data"),
+    ];
+
+    /// Returns the description of the chunk type if it is registered
+    /// by the PNG specification.
+    pub fn description(&self) -> Option<&'static str> {
+        Self::REGISTERED
+            .iter()
+            .find(|(name, _)| name.as_bytes() == self.bytes)
+            .map(|(_, description)| *description)
+    }
+
     /// Returns the chunk type as bytes.
     pub fn bytes(&self) -> [u8; 4] {
         self.bytes
     }
 
+    /// Creates a chunk type from any four bytes, without checking that they are ASCII letters.
+    ///
+    /// Unlike [ChunkType::try_from] and [ChunkType::from_str], this never fails,
+    /// which is meant for fuzzing or for reproducing malformed files.
+    /// The result may not be [valid](ChunkType::is_valid) and may not display as text.
+    pub const fn from_bytes_unchecked(bytes: [u8; 4]) -> ChunkType {
+        ChunkType { bytes }
+    }
+
     /// Checks validity of the chunk type.
     pub fn is_valid(&self) -> bool {
         self.bytes.iter().all(|b| b.is_ascii_alphabetic()) && self.is_reserved_bit_valid()
@@ -82,9 +196,53 @@ impl ChunkType {
     }
 
     /// Checks if the chunk is safe to copy.
+    ///
+    /// Editors that do not recognize a chunk may only copy it
+    /// to a modified file if it is safe to copy.
+    ///
+    /// ```
+    /// # use pngyou::ChunkType;
+    /// # use std::str::FromStr;
+    /// # fn main() -> anyhow::Result<()> {
+    /// assert!(ChunkType::from_str("RuSt")?.is_safe_to_copy());
+    /// assert!(!ChunkType::from_str("IDAT")?.is_safe_to_copy());
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn is_safe_to_copy(&self) -> bool {
         self.bytes[3].is_ascii_lowercase()
     }
+
+    /// Returns a copy of the chunk type with the case of each letter
+    /// adjusted so that it carries the given property flags.
+    pub fn with_properties(&self, properties: ChunkProperties) -> ChunkType {
+        let flags = [
+            properties.critical,
+            properties.public,
+            properties.reserved_valid,
+            !properties.safe_to_copy,
+        ];
+
+        let mut bytes = self.bytes;
+        for (byte, uppercase) in bytes.iter_mut().zip(flags) {
+            *byte = match uppercase {
+                true => byte.to_ascii_uppercase(),
+                false => byte.to_ascii_lowercase(),
+            };
+        }
+
+        ChunkType { bytes }
+    }
+
+    /// Returns all property flags of the chunk type at once.
+    pub fn properties(&self) -> ChunkProperties {
+        ChunkProperties {
+            critical: self.is_critical(),
+            public: self.is_public(),
+            reserved_valid: self.is_reserved_bit_valid(),
+            safe_to_copy: self.is_safe_to_copy(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -108,6 +266,65 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    pub fn test_chunk_type_from_str_invalid() {
+        assert_eq!(
+            ChunkType::from_str("Ru1t").unwrap_err(),
+            PngError::InvalidChunkType {
+                chunk_type: b"Ru1t".to_vec()
+            }
+        );
+        assert!(matches!(
+            ChunkType::from_str("Rust!"),
+            Err(PngError::InvalidChunkType { .. })
+        ));
+    }
+
+    #[test]
+    pub fn test_chunk_type_from_numeric_bytes() {
+        let bytes = [82, 49, 83, 116];
+
+        assert_eq!(
+            ChunkType::try_from(bytes).unwrap_err(),
+            PngError::InvalidChunkType {
+                chunk_type: bytes.to_vec()
+            }
+        );
+
+        let chunk_type = ChunkType::from_bytes_unchecked(bytes);
+        assert_eq!(chunk_type.bytes(), bytes);
+        assert!(!chunk_type.is_valid());
+    }
+
+    #[test]
+    pub fn test_chunk_type_reserved_bit_accepted() {
+        let from_bytes = ChunkType::try_from(*b"Rust").unwrap();
+        let from_str = ChunkType::from_str("Rust").unwrap();
+
+        assert_eq!(from_bytes, from_str);
+        assert!(!from_str.is_reserved_bit_valid());
+        assert!(!from_str.is_valid());
+    }
+
+    #[test]
+    pub fn test_chunk_type_constants() {
+        assert_eq!(ChunkType::from_str("IHDR").unwrap(), ChunkType::IHDR);
+        assert_eq!(ChunkType::from_str("PLTE").unwrap(), ChunkType::PLTE);
+        assert_eq!(ChunkType::from_str("IDAT").unwrap(), ChunkType::IDAT);
+        assert_eq!(ChunkType::from_str("IEND").unwrap(), ChunkType::IEND);
+        assert_ne!(ChunkType::from_str("iDAT").unwrap(), ChunkType::IDAT);
+        assert_eq!(ChunkType::from_str("tEXt").unwrap(), ChunkType::TEXT);
+        assert_eq!(ChunkType::from_str("tIME").unwrap(), ChunkType::TIME);
+        assert_eq!(ChunkType::from_str("pHYs").unwrap(), ChunkType::PHYS);
+        assert_eq!(ChunkType::from_str("bKGD").unwrap(), ChunkType::BKGD);
+
+        assert!(
+            ChunkType::STANDARD_CRITICAL
+                .iter()
+                .all(|chunk_type| chunk_type.is_critical() && chunk_type.is_valid())
+        );
+    }
+
     #[test]
     pub fn test_chunk_type_is_critical() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -171,6 +388,51 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_chunk_type_description() {
+        let chunk = ChunkType::from_str("IDAT").unwrap();
+        assert_eq!(chunk.description(), Some("image data"));
+
+        let chunk = ChunkType::from_str("RuSt").unwrap();
+        assert_eq!(chunk.description(), None);
+    }
+
+    #[test]
+    pub fn test_chunk_type_properties() {
+        let chunk = ChunkType::from_str("RuSt").unwrap();
+        assert_eq!(
+            chunk.properties(),
+            ChunkProperties {
+                critical: true,
+                public: false,
+                reserved_valid: true,
+                safe_to_copy: true,
+            }
+        );
+
+        let chunk = ChunkType::from_str("IDAT").unwrap();
+        assert_eq!(
+            chunk.properties(),
+            ChunkProperties {
+                critical: true,
+                public: true,
+                reserved_valid: true,
+                safe_to_copy: false,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_chunk_type_with_properties() {
+        let original = ChunkType::from_str("ruSt").unwrap();
+        let renamed = ChunkType::from_str("DATA")
+            .unwrap()
+            .with_properties(original.properties());
+
+        assert_eq!(&renamed.to_string(), "daTa");
+        assert_eq!(renamed.properties(), original.properties());
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
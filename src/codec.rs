@@ -0,0 +1,274 @@
+use anyhow::{Result, bail};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::io::{Read, Write};
+
+/// The [PayloadCodec] trait describes a reversible transformation applied
+/// to a payload before it is embedded, such as compression, encryption or framing.
+pub trait PayloadCodec {
+    /// Transforms the payload before it is embedded.
+    ///
+    /// # Error
+    /// Returns an error if the transformation fails, such as when encryption fails.
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Reverses the transformation on an embedded payload.
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// A codec that leaves the payload unchanged.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct IdentityCodec;
+
+impl PayloadCodec for IdentityCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+/// A codec that compresses the payload using raw deflate.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct DeflateCodec;
+
+impl PayloadCodec for DeflateCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::<u8>::new();
+        DeflateDecoder::new(data).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    }
+}
+
+impl DeflateCodec {
+    /// Decompresses the payload like [PayloadCodec::decode],
+    /// stopping as soon as the output would go over `max_size` bytes.
+    ///
+    /// # Error
+    /// Returns an error if the data is not valid deflate
+    /// or decompresses to more than `max_size` bytes.
+    pub fn decode_with_limit(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>> {
+        let limit = (max_size as u64).saturating_add(1);
+        let mut decoded = Vec::<u8>::new();
+        DeflateDecoder::new(data)
+            .take(limit)
+            .read_to_end(&mut decoded)?;
+
+        if decoded.len() > max_size {
+            bail!(
+                "Decompressed payload is over the limit of {} bytes.",
+                max_size
+            );
+        }
+        Ok(decoded)
+    }
+}
+
+/// A codec that prefixes the payload with its length as a 4-byte big-endian integer,
+/// so decoding recovers exactly the original bytes and discards any trailing padding.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct FramedCodec;
+
+impl PayloadCodec for FramedCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let Ok(length) = u32::try_from(data.len()) else {
+            bail!("Payload length exceeds 4 GiB.");
+        };
+
+        let mut framed = Vec::<u8>::with_capacity(4 + data.len());
+        framed.extend_from_slice(&length.to_be_bytes());
+        framed.extend_from_slice(data);
+        Ok(framed)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if data.len() < 4 {
+            bail!("Framed payload is too short to hold its length.");
+        }
+
+        let length = u32::from_be_bytes(data[0..4].try_into()?) as usize;
+        match data[4..].get(..length) {
+            Some(payload) => Ok(payload.to_vec()),
+            None => bail!(
+                "Framed payload declares {} bytes, found {}.",
+                length,
+                data.len() - 4
+            ),
+        }
+    }
+}
+
+/// A codec that prefixes the payload with a magic tag, so payloads written
+/// by this crate can be told apart from unrelated data in the same chunk type.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TaggedCodec {
+    magic: Vec<u8>,
+}
+
+impl Default for TaggedCodec {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAGIC.to_vec())
+    }
+}
+
+impl TaggedCodec {
+    /// The magic tag used by default.
+    pub const DEFAULT_MAGIC: &'static [u8] = b"PYOU";
+
+    /// Creates a new [TaggedCodec] using the given magic tag.
+    pub fn new(magic: Vec<u8>) -> TaggedCodec {
+        Self { magic }
+    }
+
+    /// Checks if the data starts with the magic tag.
+    pub fn is_tagged(&self, data: &[u8]) -> bool {
+        data.starts_with(&self.magic)
+    }
+}
+
+impl PayloadCodec for TaggedCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok([self.magic.as_slice(), data].concat())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match data.strip_prefix(self.magic.as_slice()) {
+            Some(payload) => Ok(payload.to_vec()),
+            None => bail!("Payload is missing the magic tag."),
+        }
+    }
+}
+
+/// A codec that layers several codecs, encoding with each one in order
+/// and decoding with each one in reverse order.
+#[derive(Default)]
+pub struct ChainCodec {
+    codecs: Vec<Box<dyn PayloadCodec>>,
+}
+
+impl ChainCodec {
+    /// Creates a new [ChainCodec] from codecs listed in encoding order.
+    pub fn new(codecs: Vec<Box<dyn PayloadCodec>>) -> ChainCodec {
+        Self { codecs }
+    }
+}
+
+impl PayloadCodec for ChainCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.codecs
+            .iter()
+            .try_fold(data.to_vec(), |data, codec| codec.encode(&data))
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.codecs
+            .iter()
+            .rev()
+            .try_fold(data.to_vec(), |data, codec| codec.decode(&data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::Png;
+    use std::str::FromStr;
+
+    struct XorCodec(u8);
+
+    impl PayloadCodec for XorCodec {
+        fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.iter().map(|b| b ^ self.0).collect())
+        }
+
+        fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+            self.encode(data)
+        }
+    }
+
+    #[test]
+    fn test_identity_codec() {
+        let codec = IdentityCodec;
+        assert_eq!(codec.encode(b"secret").unwrap(), b"secret");
+        assert_eq!(codec.decode(b"secret").unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_deflate_codec() {
+        let codec = DeflateCodec;
+        let data = "secret ".repeat(100).into_bytes();
+
+        let encoded = codec.encode(&data).unwrap();
+        assert!(encoded.len() < data.len());
+        assert_eq!(codec.decode(&encoded).unwrap(), data);
+
+        assert!(codec.decode(&[0xFF; 16]).is_err());
+    }
+
+    #[test]
+    fn test_deflate_codec_limit() {
+        let codec = DeflateCodec;
+        let encoded = codec.encode(&[0; 1000]).unwrap();
+
+        assert_eq!(codec.decode_with_limit(&encoded, 1000).unwrap().len(), 1000);
+        assert!(codec.decode_with_limit(&encoded, 999).is_err());
+    }
+
+    #[test]
+    fn test_framed_codec_discards_padding() {
+        let codec = FramedCodec;
+        let data = b"exact \x00 binary";
+
+        let mut padded = codec.encode(data).unwrap();
+        padded.extend_from_slice(&[0; 16]);
+
+        assert_eq!(codec.decode(&padded).unwrap(), data);
+        assert!(codec.decode(&padded[..10]).is_err());
+        assert!(codec.decode(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_tagged_codec() {
+        let codec = TaggedCodec::default();
+        let encoded = codec.encode(b"secret").unwrap();
+
+        assert_eq!(encoded, b"PYOUsecret");
+        assert!(codec.is_tagged(&encoded));
+        assert_eq!(codec.decode(&encoded).unwrap(), b"secret");
+        assert!(codec.decode(b"secret").is_err());
+
+        let custom = TaggedCodec::new(b"MINE".to_vec());
+        assert!(!custom.is_tagged(&encoded));
+    }
+
+    #[test]
+    fn test_chain_codec_round_trip() {
+        let codec = ChainCodec::new(vec![Box::new(DeflateCodec), Box::new(XorCodec(0x5A))]);
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = "layered secret ".repeat(10).into_bytes();
+
+        let mut png = Png::from_chunks(vec![]);
+        png.append_with_codec(chunk_type.clone(), &data, &codec)
+            .unwrap();
+
+        let stored = png.chunks()[0].data();
+        assert_eq!(
+            DeflateCodec
+                .decode(&XorCodec(0x5A).encode(stored).unwrap())
+                .unwrap(),
+            data
+        );
+
+        assert_eq!(png.read_with_codec(&chunk_type, &codec).unwrap(), data);
+    }
+}
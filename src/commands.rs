@@ -1,8 +1,50 @@
-use super::args::InputImage;
+use super::args::{InputImage, OnInvalid};
 use anyhow::{Result, bail};
-use pngyou::{Chunk, ChunkType, Png};
-use std::fs;
-use std::path::PathBuf;
+use pngyou::{
+    ChainCodec, Chunk, ChunkType, FramedCodec, PayloadCodec, Png, PngError, TaggedCodec, carve_png,
+};
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Errors the CLI reports with a dedicated exit code.
+#[derive(Debug)]
+pub enum CliError {
+    /// The file was read fine but failed a check.
+    Validation(String),
+    /// The command line asked for something that cannot be done.
+    Usage(String),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Validation(message) | Self::Usage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Exit code for successful runs.
+pub const EXIT_SUCCESS: u8 = 0;
+/// Exit code for files failing validation.
+pub const EXIT_VALIDATION: u8 = 1;
+/// Exit code for IO and parse errors.
+pub const EXIT_ERROR: u8 = 2;
+/// Exit code for usage errors.
+pub const EXIT_USAGE: u8 = 3;
+
+/// Maps an error to the exit code of its category.
+/// Errors not raised as a [CliError] come from IO or parsing.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    match err.downcast_ref::<CliError>() {
+        Some(CliError::Validation(_)) => EXIT_VALIDATION,
+        Some(CliError::Usage(_)) => EXIT_USAGE,
+        None => EXIT_ERROR,
+    }
+}
 
 fn parse_input(input: &InputImage) -> Result<Vec<u8>> {
     let mut bytes = Vec::<u8>::new();
@@ -10,7 +52,7 @@ fn parse_input(input: &InputImage) -> Result<Vec<u8>> {
     match input {
         InputImage::File(path) => {
             let file_bytes = fs::read(path)?;
-            bytes.extend_from_slice(&file_bytes.as_slice());
+            bytes.extend_from_slice(file_bytes.as_slice());
         }
         InputImage::Url(url) => {
             let mut response = ureq::get(url).call()?;
@@ -23,22 +65,77 @@ fn parse_input(input: &InputImage) -> Result<Vec<u8>> {
     Ok(bytes)
 }
 
+/// Options controlling how the encode command embeds and writes a message.
+#[derive(Clone, Debug, Default)]
+pub struct EncodeOptions {
+    /// Re-parse the encoded output and check the message before writing.
+    pub verify: bool,
+    /// Prefix the message with its length.
+    pub framed: bool,
+    /// Prefix the message with a magic tag.
+    pub tagged: bool,
+    /// Compress the message before embedding it.
+    pub compress: bool,
+    /// Encrypt the message with a key derived from the password.
+    pub password: Option<String>,
+    /// Print what would change instead of writing the output.
+    pub dry_run: bool,
+    /// Permissions for the output file.
+    pub mode: Option<u32>,
+}
+
 pub fn encode(
     input: &InputImage,
     output: &Option<PathBuf>,
     chunk_type: &ChunkType,
     message: &str,
+    options: &EncodeOptions,
+) -> Result<()> {
+    encode_with(input, output, chunk_type, message, options, Png::as_bytes)
+}
+
+/// Same as [encode], serializing the encoded PNG with the given function,
+/// so tests can simulate a broken write path.
+fn encode_with(
+    input: &InputImage,
+    output: &Option<PathBuf>,
+    chunk_type: &ChunkType,
+    message: &str,
+    options: &EncodeOptions,
+    serialize: fn(&Png) -> Vec<u8>,
 ) -> Result<()> {
     let parsed_input = parse_input(input)?;
     let mut png = Png::try_from(parsed_input.as_slice())?;
 
-    let data = message.bytes().collect::<Vec<u8>>();
+    let codec = payload_codec(
+        options.compress,
+        options.framed,
+        options.tagged,
+        options.password.as_deref(),
+    )?;
+    let data = codec.encode(message.as_bytes())?;
 
-    let chunk_to_append = Chunk::new(chunk_type.clone(), data);
-    png.append_chunk(chunk_to_append);
+    let original_len = png.total_len();
+    let chunk_to_append = Chunk::new(chunk_type.clone(), data.clone());
+    png.append_chunk_checked(chunk_to_append)?;
+
+    let bytes = serialize(&png);
+    if options.verify {
+        verify_encoded(&bytes, chunk_type, &data)?;
+    }
+
+    if options.dry_run {
+        println!(
+            "Would append 1 chunk ({}, {} bytes) before IEND; file would grow by {} bytes.",
+            chunk_type,
+            data.len(),
+            bytes.len() - original_len
+        );
+        return Ok(());
+    }
 
     match output {
-        Some(output) => Ok(fs::write(output, png.as_bytes())?),
+        Some(output) => write_output(output, &bytes, options.mode),
         None => {
             println!("{}", png);
             Ok(())
@@ -46,35 +143,238 @@ pub fn encode(
     }
 }
 
-pub fn decode(input: &InputImage, chunk_type: &ChunkType) -> Result<()> {
+/// Builds the codec applying the payload transformations selected on the command line.
+/// The tag, when used, is always outermost so it can be checked before decoding the rest.
+///
+/// Compression is innermost. Decoding leaves it out, since compressed messages
+/// carry a marker and are detected by [decompress_if_compressed] instead.
+fn payload_codec(
+    compress: bool,
+    framed: bool,
+    tagged: bool,
+    password: Option<&str>,
+) -> Result<ChainCodec> {
+    let mut codecs = Vec::<Box<dyn PayloadCodec>>::new();
+    if compress {
+        codecs.push(compression_codec()?);
+    }
+    if framed {
+        codecs.push(Box::new(FramedCodec));
+    }
+    if let Some(password) = password {
+        codecs.push(encryption_codec(password)?);
+    }
+    if tagged {
+        codecs.push(Box::new(TaggedCodec::default()));
+    }
+
+    Ok(ChainCodec::new(codecs))
+}
+
+#[cfg(feature = "compression")]
+fn compression_codec() -> Result<Box<dyn PayloadCodec>> {
+    Ok(Box::new(pngyou::CompressionCodec::default()))
+}
+
+#[cfg(not(feature = "compression"))]
+fn compression_codec() -> Result<Box<dyn PayloadCodec>> {
+    bail!(CliError::Usage(
+        "Compressing the message requires the compression feature.".to_string()
+    ));
+}
+
+/// Decompresses messages written with --compress, which start with
+/// [pngyou::CompressionCodec::MAGIC], leaving any other message as is.
+#[cfg(feature = "compression")]
+fn decompress_if_compressed(data: Vec<u8>) -> Result<Vec<u8>> {
+    let codec = pngyou::CompressionCodec::default();
+    match codec.is_compressed(&data) {
+        true => codec.decode(&data),
+        false => Ok(data),
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress_if_compressed(data: Vec<u8>) -> Result<Vec<u8>> {
+    Ok(data)
+}
+
+#[cfg(feature = "encryption")]
+fn encryption_codec(password: &str) -> Result<Box<dyn PayloadCodec>> {
+    Ok(Box::new(pngyou::AesGcmCodec::new(password)))
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encryption_codec(_password: &str) -> Result<Box<dyn PayloadCodec>> {
+    bail!(CliError::Usage(
+        "Encrypting with a password requires the encryption feature.".to_string()
+    ));
+}
+
+/// Re-parses encoded bytes and checks that the last chunk
+/// of the given type holds exactly the encoded data.
+fn verify_encoded(bytes: &[u8], chunk_type: &ChunkType, data: &[u8]) -> Result<()> {
+    let png = match Png::try_from(bytes) {
+        Ok(png) => png,
+        Err(err) => bail!(CliError::Validation(format!(
+            "Verification failed! Encoded output does not parse: {}",
+            err
+        ))),
+    };
+
+    match png.chunks_by_type(chunk_type).last() {
+        Some(chunk) if chunk.data() == data => Ok(()),
+        Some(_) => bail!(CliError::Validation(
+            "Verification failed! Decoded message does not match.".to_string()
+        )),
+        None => bail!(CliError::Validation(format!(
+            "Verification failed! No chunk found of type: {}",
+            chunk_type
+        ))),
+    }
+}
+
+/// Options controlling how the decode command reads messages.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// Text encoding of the message.
+    pub encoding: Option<String>,
+    /// Read the message length from its prefix and discard any padding.
+    pub framed: bool,
+    /// Only read messages carrying the magic tag.
+    pub tagged: bool,
+    /// Decrypt the message with a key derived from the password.
+    pub password: Option<String>,
+    /// How to print messages that are not valid UTF-8.
+    pub on_invalid: OnInvalid,
+}
+
+pub fn decode(input: &InputImage, chunk_type: &ChunkType, options: &DecodeOptions) -> Result<()> {
     let parsed_input = parse_input(input)?;
     let png = Png::try_from(parsed_input.as_slice())?;
 
-    let chunks = png.chunks_by_type(chunk_type);
-    if chunks.is_empty() {
-        bail!("No chunk found of type:\n{}", chunk_type);
+    for message in decode_messages(&png, chunk_type, options)? {
+        match &options.encoding {
+            Some(label) => println!("{}", data_as_encoded(&message, label)?),
+            None => println!("{}", format_data(message.data(), options.on_invalid)?),
+        }
     }
+    Ok(())
+}
 
-    chunks.into_iter().for_each(|chunk| {
-        if let Ok(message) = String::from_utf8(chunk.data().to_vec()) {
-            println!("{}", message);
-        } else {
-            println!("[Hex data]: {:?}", chunk.data());
+/// Decodes the data of every chunk of the given type, skipping
+/// untagged chunks when only tagged messages are wanted.
+fn decode_messages(
+    png: &Png,
+    chunk_type: &ChunkType,
+    options: &DecodeOptions,
+) -> Result<Vec<Chunk>> {
+    let chunks: Vec<&Chunk> = png
+        .chunks_by_type(chunk_type)
+        .into_iter()
+        .filter(|chunk| !options.tagged || TaggedCodec::default().is_tagged(chunk.data()))
+        .collect();
+
+    if chunks.is_empty() {
+        match options.tagged {
+            true => bail!("No tagged chunk found of type:\n{}", chunk_type),
+            false => bail!("No chunk found of type:\n{}", chunk_type),
         }
-    });
-    Ok(())
+    }
+
+    let codec = payload_codec(
+        false,
+        options.framed,
+        options.tagged,
+        options.password.as_deref(),
+    )?;
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let data = decompress_if_compressed(codec.decode(chunk.data())?)?;
+            Ok(Chunk::new(chunk_type.clone(), data))
+        })
+        .collect()
+}
+
+#[cfg(feature = "encoding")]
+fn data_as_encoded(chunk: &Chunk, label: &str) -> Result<String> {
+    chunk.data_as_encoded(label)
 }
 
-pub fn remove(input: &InputImage, output: &Option<PathBuf>, chunk_type: &ChunkType) -> Result<()> {
+#[cfg(not(feature = "encoding"))]
+fn data_as_encoded(_chunk: &Chunk, _label: &str) -> Result<String> {
+    bail!(CliError::Usage(
+        "Decoding with a text encoding requires the encoding feature.".to_string()
+    ));
+}
+
+/// Formats the message as text, falling back to the given
+/// representation when it is not valid UTF-8.
+fn format_data(data: &[u8], on_invalid: OnInvalid) -> Result<String> {
+    if let Ok(message) = str::from_utf8(data) {
+        return Ok(message.to_string());
+    }
+
+    match on_invalid {
+        OnInvalid::Hex => Ok(data.iter().map(|b| format!("{:02x}", b)).collect()),
+        OnInvalid::Base64 => data_as_base64(data),
+        OnInvalid::Lossy => Ok(String::from_utf8_lossy(data).into_owned()),
+        OnInvalid::Error => bail!("Message of {} bytes is not valid UTF-8.", data.len()),
+    }
+}
+
+#[cfg(feature = "base64")]
+fn data_as_base64(data: &[u8]) -> Result<String> {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+
+    Ok(STANDARD.encode(data))
+}
+
+#[cfg(not(feature = "base64"))]
+fn data_as_base64(_data: &[u8]) -> Result<String> {
+    bail!(CliError::Usage(
+        "Printing messages as base64 requires the base64 feature.".to_string()
+    ));
+}
+
+pub fn remove(
+    input: &InputImage,
+    output: &Option<PathBuf>,
+    chunk_type: &ChunkType,
+    dry_run: bool,
+    mode: Option<u32>,
+) -> Result<()> {
     let parsed_input = parse_input(input)?;
     let mut png = Png::try_from(parsed_input.as_slice())?;
 
+    if dry_run {
+        let Some(index) = png
+            .chunks()
+            .iter()
+            .position(|chunk| chunk.chunk_type() == chunk_type)
+        else {
+            bail!(PngError::ChunkNotFound {
+                chunk_type: chunk_type.clone()
+            });
+        };
+
+        println!(
+            "Would remove first {} chunk at index {}; file would shrink by {} bytes.",
+            chunk_type,
+            index,
+            12 + png.chunks()[index].data().len()
+        );
+        return Ok(());
+    }
+
     png.remove_first_chunk(chunk_type)?;
 
     match output {
-        Some(output) => Ok(fs::write(output, png.as_bytes())?),
+        Some(output) => write_output(output, &png.as_bytes(), mode),
         None => match input {
-            InputImage::File(path) => Ok(fs::write(path, png.as_bytes())?),
+            InputImage::File(path) => write_output(path, &png.as_bytes(), mode),
             InputImage::Url(_) => {
                 println!("{}", png);
                 Ok(())
@@ -83,10 +383,874 @@ pub fn remove(input: &InputImage, output: &Option<PathBuf>, chunk_type: &ChunkTy
     }
 }
 
-pub fn print(input: &InputImage) -> Result<()> {
+pub fn strip(
+    input: &InputImage,
+    output: &Option<PathBuf>,
+    trailer: bool,
+    mode: Option<u32>,
+) -> Result<()> {
+    if !trailer {
+        bail!(CliError::Usage(
+            "Nothing to strip! Pass --trailer to remove data after IEND.".to_string()
+        ));
+    }
+
+    let parsed_input = parse_input(input)?;
+    let mut png = Png::try_from(parsed_input.as_slice())?;
+    png.clear_trailer();
+
+    match output {
+        Some(output) => write_output(output, &png.as_bytes(), mode),
+        None => match input {
+            InputImage::File(path) => write_output(path, &png.as_bytes(), mode),
+            InputImage::Url(_) => {
+                println!("{}", png);
+                Ok(())
+            }
+        },
+    }
+}
+
+pub fn print(input: &InputImage, json: bool) -> Result<()> {
     let parsed_input = parse_input(input)?;
     let png = Png::try_from(parsed_input.as_slice())?;
 
-    println!("{}", png);
+    match json {
+        true => println!("{}", png_json(&png)?),
+        false => println!("{}", png),
+    }
     Ok(())
 }
+
+/// Describes the image dimensions and every chunk of the PNG as JSON.
+/// The dimensions are null when the IHDR chunk is missing or malformed.
+#[cfg(feature = "json")]
+fn png_json(png: &Png) -> Result<String> {
+    use serde_json::json;
+
+    let (width, height) = png.dimensions().ok().unzip();
+    let chunks: Vec<serde_json::Value> = png
+        .chunks()
+        .iter()
+        .map(|chunk| {
+            let chunk_type = chunk.chunk_type();
+            json!({
+                "type": chunk_type.to_string(),
+                "length": chunk.length(),
+                "crc": chunk.crc(),
+                "critical": chunk_type.is_critical(),
+                "safe_to_copy": chunk_type.is_safe_to_copy(),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "width": width,
+        "height": height,
+        "chunks": chunks,
+    })
+    .to_string())
+}
+
+#[cfg(not(feature = "json"))]
+fn png_json(_png: &Png) -> Result<String> {
+    bail!(CliError::Usage(
+        "Printing as JSON requires the json feature.".to_string()
+    ));
+}
+
+pub fn list(input: &InputImage) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let png = Png::try_from(parsed_input.as_slice())?;
+
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        println!(
+            "{}: {} ({} bytes) CRC: {:#010x}",
+            index,
+            chunk.chunk_type(),
+            chunk.length(),
+            chunk.crc()
+        );
+    }
+    Ok(())
+}
+
+pub fn map(input: &InputImage, width: usize) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let png = Png::try_from(parsed_input.as_slice())?;
+
+    println!("{}", png.ascii_map_with_width(width));
+    Ok(())
+}
+
+pub fn top(input: &InputImage, count: usize) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let png = Png::try_from(parsed_input.as_slice())?;
+
+    for chunk in png.chunks_sorted_by_size().into_iter().take(count) {
+        println!("{}\t{} bytes", chunk.chunk_type(), chunk.length());
+    }
+    Ok(())
+}
+
+pub fn identify(input: &InputImage) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let png = Png::try_from(parsed_input.as_slice())?;
+
+    let traces = pngyou_traces(&png);
+    if traces.is_empty() {
+        println!("No signs of pngyou found.");
+        return Ok(());
+    }
+
+    println!("Likely created or modified by pngyou:");
+    for (chunk_type, reason) in traces {
+        println!("  {}\t{}", chunk_type, reason);
+    }
+    Ok(())
+}
+
+/// Finds unregistered chunks whose data carries the magic tag, the message
+/// framing or the length prefix this tool writes, along with what was found.
+fn pngyou_traces(png: &Png) -> Vec<(ChunkType, &'static str)> {
+    let length_prefix = |data: &[u8]| {
+        data.first_chunk::<4>()
+            .map(|prefix| u32::from_be_bytes(*prefix) as usize)
+    };
+
+    png.chunks()
+        .iter()
+        .filter(|chunk| chunk.chunk_type().description().is_none())
+        .filter_map(|chunk| {
+            let data = chunk.data();
+            let reason = if TaggedCodec::default().is_tagged(data) {
+                "tagged message"
+            } else if data.first() == Some(&Png::MESSAGE_VERSION)
+                && length_prefix(&data[1..]) == Some(data.len().saturating_sub(5))
+            {
+                "versioned message"
+            } else if length_prefix(data) == Some(data.len().saturating_sub(4)) {
+                "framed message"
+            } else {
+                return None;
+            };
+
+            Some((chunk.chunk_type().clone(), reason))
+        })
+        .collect()
+}
+
+pub fn verify(input: &InputImage) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let png = Png::try_from(parsed_input.as_slice())?;
+
+    for warning in png.lint() {
+        println!("Warning: {}", warning);
+    }
+
+    match png.validate() {
+        Ok(()) => {
+            println!("No issues found.");
+            Ok(())
+        }
+        Err(issues) => {
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            bail!(CliError::Validation(format!(
+                "Validation failed! Found {} issues.",
+                issues.len()
+            )))
+        }
+    }
+}
+
+pub fn list_types() -> Result<()> {
+    print!("{}", registered_types());
+    Ok(())
+}
+
+fn registered_types() -> String {
+    ChunkType::REGISTERED
+        .iter()
+        .map(|(name, description)| format!("{}\t{}\n", name, description))
+        .collect()
+}
+
+pub fn extract_png(input: &InputImage, output: &Option<PathBuf>, mode: Option<u32>) -> Result<()> {
+    let parsed_input = parse_input(input)?;
+    let carved = carve_png(parsed_input.as_slice())?;
+    let png = Png::try_from(carved)?;
+
+    match output {
+        Some(output) => write_output(output, carved, mode),
+        None => {
+            println!("{}", png);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+pub fn cover(
+    output: &Path,
+    width: u32,
+    height: u32,
+    noise: bool,
+    color: [u8; 3],
+    mode: Option<u32>,
+) -> Result<()> {
+    let bytes = cover_bytes(width, height, noise, color)?;
+    write_output(output, &bytes, mode)
+}
+
+/// Encodes a solid color or random noise RGB image as PNG bytes.
+/// Noise hides LSB modifications better than a flat color.
+#[cfg(feature = "image")]
+fn cover_bytes(width: u32, height: u32, noise: bool, color: [u8; 3]) -> Result<Vec<u8>> {
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    let image = match noise {
+        true => RgbImage::from_fn(width, height, |_, _| Rgb(rand::random())),
+        false => RgbImage::from_pixel(width, height, Rgb(color)),
+    };
+
+    let mut bytes = Vec::<u8>::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// Atomically writes bytes to the output path, optionally setting its permissions.
+fn write_output(path: &Path, bytes: &[u8], mode: Option<u32>) -> Result<()> {
+    write_atomically(path, mode, |file| file.write_all(bytes))
+}
+
+/// Writes to a temporary file in the same directory as the output path and
+/// renames it over the output once complete, so a failure mid-write
+/// never leaves a truncated file behind.
+///
+/// A symlinked output is resolved first so the link itself is kept, and an
+/// existing output keeps its permissions unless `mode` overrides them.
+fn write_atomically(
+    path: &Path,
+    mode: Option<u32>,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> Result<()> {
+    let path = &match fs::canonicalize(path) {
+        Ok(target) => target,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(err) => return Err(err.into()),
+    };
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => bail!("Invalid output path: {}", path.display()),
+    };
+    let (temp_path, mut file) = create_temp_file(path, &file_name)?;
+
+    let result = write(&mut file)
+        .and_then(|_| file.sync_all())
+        .map_err(Into::into)
+        .and_then(|_| {
+            if let Some(permissions) = permissions {
+                fs::set_permissions(&temp_path, permissions)?;
+            }
+            set_mode(&temp_path, mode)
+        })
+        .and_then(|_| Ok(fs::rename(&temp_path, path)?));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// How many temporary file names [create_temp_file] tries before giving up.
+const TEMP_FILE_ATTEMPTS: u32 = 100;
+
+/// Creates a new temporary file next to the output path.
+///
+/// An existing file or symlink is never opened, so another user of a shared
+/// directory cannot redirect the write by planting one under the expected name;
+/// the next name is tried instead.
+fn create_temp_file(path: &Path, file_name: &str) -> Result<(PathBuf, File)> {
+    for attempt in 0..TEMP_FILE_ATTEMPTS {
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            attempt
+        ));
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    bail!(
+        "Could not create a temporary file next to {}",
+        path.display()
+    );
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, mode: Option<u32>) -> Result<()> {
+    if mode.is_some() {
+        bail!(CliError::Usage(
+            "Setting the output mode is only supported on Unix.".to_string()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "watch")]
+pub fn watch(input: &Path, chunk_type: &ChunkType) -> Result<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use std::sync::mpsc;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    // Watch the parent directory rather than the file itself, so the watch
+    // survives editors and tools that atomically replace the file.
+    let directory = match input.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    let mut seen = Vec::<Vec<u8>>::new();
+    print_new_payloads(input, chunk_type, &mut seen);
+
+    for event in rx {
+        let event = event?;
+
+        if matches!(event.kind, EventKind::Access(_))
+            || !event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == input.file_name())
+        {
+            continue;
+        }
+
+        print_new_payloads(input, chunk_type, &mut seen);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "watch")]
+fn print_new_payloads(input: &Path, chunk_type: &ChunkType, seen: &mut Vec<Vec<u8>>) {
+    let bytes = match fs::read(input) {
+        Ok(bytes) => bytes,
+        // The file is briefly absent while being atomically replaced.
+        Err(err) if err.kind() == ErrorKind::NotFound => return,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", input.display(), err);
+            return;
+        }
+    };
+
+    let png = match Png::try_from(bytes.as_slice()) {
+        Ok(png) => png,
+        Err(err) => {
+            eprintln!("Failed to parse {}: {}", input.display(), err);
+            return;
+        }
+    };
+
+    let payloads: Vec<Vec<u8>> = png
+        .chunks_by_type(chunk_type)
+        .into_iter()
+        .map(|chunk| chunk.data().to_vec())
+        .collect();
+
+    payloads
+        .iter()
+        .filter(|payload| !seen.contains(payload))
+        .filter_map(|payload| format_data(payload, OnInvalid::Hex).ok())
+        .for_each(|message| println!("{}", message));
+
+    *seen = payloads;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    /// A path in the temporary directory whose file is removed when dropped,
+    /// so a failing assertion does not leave it behind.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("pngyou-{}-{}", std::process::id(), name)))
+        }
+    }
+
+    impl std::ops::Deref for TempFile {
+        type Target = PathBuf;
+
+        fn deref(&self) -> &PathBuf {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempFile {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Writes the PNG to a temporary file, encodes the message into it
+    /// and parses the output back.
+    fn encode_file(
+        name: &str,
+        png: &Png,
+        chunk_type: &ChunkType,
+        message: &str,
+        options: &EncodeOptions,
+    ) -> Png {
+        let input = TempFile::new(&format!("{}-input.png", name));
+        let output = TempFile::new(&format!("{}-output.png", name));
+        fs::write(&input, png.as_bytes()).unwrap();
+
+        encode(
+            &InputImage::File(input.to_path_buf()),
+            &Some(output.to_path_buf()),
+            chunk_type,
+            message,
+            options,
+        )
+        .unwrap();
+
+        Png::try_from(fs::read(&output).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_encode_with_verify() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let options = EncodeOptions {
+            verify: true,
+            ..Default::default()
+        };
+        let png = encode_file("verify", &testing_png(), &chunk_type, "secret", &options);
+        assert_eq!(png.chunks_by_type(&chunk_type)[0].data(), b"secret");
+    }
+
+    #[test]
+    fn test_encode_verify_catches_corrupted_output() {
+        let input = TempFile::new("verify-corrupt-input.png");
+        let output = TempFile::new("verify-corrupt-output.png");
+        fs::write(&input, testing_png().as_bytes()).unwrap();
+
+        let corrupt = |png: &Png| {
+            let mut bytes = png.as_bytes();
+            *bytes.last_mut().unwrap() ^= 0xFF;
+            bytes
+        };
+        let options = EncodeOptions {
+            verify: true,
+            ..Default::default()
+        };
+        let err = encode_with(
+            &InputImage::File(input.to_path_buf()),
+            &Some(output.to_path_buf()),
+            &ChunkType::from_str("RuSt").unwrap(),
+            "secret",
+            &options,
+            corrupt,
+        )
+        .unwrap_err();
+
+        assert_eq!(exit_code(&err), EXIT_VALIDATION);
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_encode_framed() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let options = EncodeOptions {
+            framed: true,
+            ..Default::default()
+        };
+        let png = encode_file("framed", &testing_png(), &chunk_type, "secret", &options);
+        let mut stored = png.chunks_by_type(&chunk_type)[0].data().to_vec();
+        assert_eq!(stored, b"\x00\x00\x00\x06secret");
+
+        stored.extend_from_slice(&[0; 10]);
+        assert_eq!(
+            payload_codec(false, true, false, None)
+                .unwrap()
+                .decode(&stored)
+                .unwrap(),
+            b"secret"
+        );
+    }
+
+    #[test]
+    fn test_tagged_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(chunk_type.clone(), b"untagged".to_vec()));
+
+        let options = EncodeOptions {
+            framed: true,
+            tagged: true,
+            ..Default::default()
+        };
+        let png = encode_file("tagged", &png, &chunk_type, "secret", &options);
+        let options = DecodeOptions {
+            framed: true,
+            tagged: true,
+            ..Default::default()
+        };
+        let messages = decode_messages(&png, &chunk_type, &options).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].data(), b"secret");
+    }
+
+    #[test]
+    fn test_identify() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let options = EncodeOptions {
+            tagged: true,
+            ..Default::default()
+        };
+        let png = encode_file("identify", &testing_png(), &chunk_type, "secret", &options);
+        assert_eq!(pngyou_traces(&png), vec![(chunk_type, "tagged message")]);
+
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text("Comment", "plain").unwrap());
+        assert!(pngyou_traces(&png).is_empty());
+    }
+
+    #[test]
+    fn test_identify_framing() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let mut png = testing_png();
+        png.append_message(chunk_type.clone(), b"versioned")
+            .unwrap();
+        png.append_chunk(Chunk::new(
+            chunk_type.clone(),
+            FramedCodec.encode(b"framed").unwrap(),
+        ));
+
+        assert_eq!(
+            pngyou_traces(&png),
+            vec![
+                (chunk_type.clone(), "versioned message"),
+                (chunk_type, "framed message"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encrypted_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let options = EncodeOptions {
+            framed: true,
+            password: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        let png = encode_file("encrypted", &testing_png(), &chunk_type, "secret", &options);
+        assert!(
+            !png.chunks_by_type(&chunk_type)[0]
+                .data()
+                .windows(6)
+                .any(|window| window == b"secret")
+        );
+
+        let mut options = DecodeOptions {
+            framed: true,
+            password: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        let messages = decode_messages(&png, &chunk_type, &options).unwrap();
+        assert_eq!(messages[0].data(), b"secret");
+
+        options.password = Some("wrong".to_string());
+        assert!(decode_messages(&png, &chunk_type, &options).is_err());
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    #[test]
+    fn test_password_requires_feature() {
+        match payload_codec(false, false, false, Some("hunter2")) {
+            Ok(_) => panic!("Expected encryption to require the feature."),
+            Err(err) => assert_eq!(exit_code(&err), EXIT_USAGE),
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let message = "secret ".repeat(50);
+        let options = EncodeOptions {
+            framed: true,
+            compress: true,
+            ..Default::default()
+        };
+        let png = encode_file(
+            "compressed",
+            &testing_png(),
+            &chunk_type,
+            &message,
+            &options,
+        );
+        assert!(png.chunks_by_type(&chunk_type)[0].data().len() < message.len());
+
+        let options = DecodeOptions {
+            framed: true,
+            ..Default::default()
+        };
+        let messages = decode_messages(&png, &chunk_type, &options).unwrap();
+        assert_eq!(messages[0].data(), message.as_bytes());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_keeps_unmarked_zlib() {
+        // An empty zlib stream, which must not be decompressed without the marker.
+        let data = vec![0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01];
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(chunk_type.clone(), data.clone()));
+
+        let messages = decode_messages(&png, &chunk_type, &DecodeOptions::default()).unwrap();
+        assert_eq!(messages[0].data(), data);
+    }
+
+    #[test]
+    fn test_format_data_on_invalid() {
+        let binary = [0xFF, 0xFE, b'h', b'i'];
+
+        assert_eq!(format_data(b"hello", OnInvalid::Error).unwrap(), "hello");
+        assert_eq!(format_data(&binary, OnInvalid::Hex).unwrap(), "fffe6869");
+        assert_eq!(
+            format_data(&binary, OnInvalid::Lossy).unwrap(),
+            "\u{FFFD}\u{FFFD}hi"
+        );
+        assert!(format_data(&binary, OnInvalid::Error).is_err());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_format_data_base64() {
+        let binary = [0xFF, 0xFE, b'h', b'i'];
+        assert_eq!(format_data(&binary, OnInvalid::Base64).unwrap(), "//5oaQ==");
+    }
+
+    #[cfg(not(feature = "base64"))]
+    #[test]
+    fn test_format_data_base64_requires_feature() {
+        let err = format_data(&[0xFF], OnInvalid::Base64).unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_USAGE);
+    }
+
+    #[cfg(not(feature = "json"))]
+    #[test]
+    fn test_print_json_requires_feature() {
+        let err = png_json(&testing_png()).unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_tagged_decode_skips_untagged() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(chunk_type.clone(), b"untagged".to_vec()));
+
+        let tagged = DecodeOptions {
+            tagged: true,
+            ..Default::default()
+        };
+        assert!(decode_messages(&png, &chunk_type, &tagged).is_err());
+
+        let messages = decode_messages(&png, &chunk_type, &DecodeOptions::default()).unwrap();
+        assert_eq!(messages[0].data(), b"untagged");
+    }
+
+    #[test]
+    fn test_verify_encoded_detects_corruption() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(chunk_type.clone(), b"secret".to_vec()));
+
+        let bytes = png.as_bytes();
+        assert!(verify_encoded(&bytes, &chunk_type, b"secret").is_ok());
+
+        let mut corrupted = bytes.clone();
+        let index = corrupted.len() - 20;
+        corrupted[index] ^= 0xFF;
+        assert!(verify_encoded(&corrupted, &chunk_type, b"secret").is_err());
+
+        assert!(verify_encoded(&bytes, &chunk_type, b"other").is_err());
+    }
+
+    #[test]
+    fn test_strip_trailer() {
+        let input = TempFile::new("strip-input.png");
+        let mut png = testing_png();
+        png.set_trailer(b"hidden".to_vec());
+        fs::write(&input, png.as_bytes()).unwrap();
+
+        let path = InputImage::File(input.to_path_buf());
+        assert!(strip(&path, &None, false, None).is_err());
+
+        strip(&path, &None, true, None).unwrap();
+        assert_eq!(fs::read(&input).unwrap(), testing_png().as_bytes());
+    }
+
+    #[test]
+    fn test_registered_types() {
+        let output = registered_types();
+        assert!(output.lines().any(|line| line == "IHDR\timage header"));
+        assert!(output.lines().any(|line| line == "IDAT\timage data"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_output_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let output = TempFile::new("mode-output.png");
+        write_output(&output, &testing_png().as_bytes(), Some(0o600)).unwrap();
+
+        let mode = fs::metadata(&output).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read(&output).unwrap(), testing_png().as_bytes());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_output_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let output = TempFile::new("keep-mode-output.png");
+        fs::write(&output, b"original").unwrap();
+        fs::set_permissions(&output, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_output(&output, &testing_png().as_bytes(), None).unwrap();
+
+        let mode = fs::metadata(&output).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_output_keeps_symlink() {
+        let target = TempFile::new("symlink-target.png");
+        let link = TempFile::new("symlink-link.png");
+        fs::write(&target, b"original").unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_output(&link, &testing_png().as_bytes(), None).unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read(&target).unwrap(), testing_png().as_bytes());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_output_ignores_planted_temp_file() {
+        let output = TempFile::new("planted-output.png");
+        let victim = TempFile::new("planted-victim.png");
+        let planted = TempFile(output.with_file_name(format!(
+            ".{}.{}.0.tmp",
+            output.file_name().unwrap().to_string_lossy(),
+            std::process::id()
+        )));
+        fs::write(&victim, b"victim").unwrap();
+        let _ = fs::remove_file(&planted);
+        std::os::unix::fs::symlink(&victim, &planted).unwrap();
+
+        write_output(&output, &testing_png().as_bytes(), None).unwrap();
+
+        assert_eq!(fs::read(&output).unwrap(), testing_png().as_bytes());
+        assert_eq!(fs::read(&victim).unwrap(), b"victim");
+        assert!(fs::symlink_metadata(&planted).is_ok());
+    }
+
+    #[test]
+    fn test_write_atomically_failure_keeps_original() {
+        let output = TempFile::new("atomic-output.png");
+        fs::write(&output, b"original").unwrap();
+
+        let result = write_atomically(&output, None, |file| {
+            file.write_all(b"partial")?;
+            Err(io::Error::other("simulated failure"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&output).unwrap(), b"original");
+
+        let leftovers = fs::read_dir(output.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.starts_with(".pngyou-") && name.contains("atomic-output")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_cover() {
+        let output = TempFile::new("cover.png");
+        cover(&output, 12, 7, true, [0; 3], None).unwrap();
+
+        let png = Png::try_from(fs::read(&output).unwrap().as_slice()).unwrap();
+        let ihdr = png.chunk_by_type("IHDR").unwrap().data();
+        assert_eq!(&ihdr[0..4], &12u32.to_be_bytes());
+        assert_eq!(&ihdr[4..8], &7u32.to_be_bytes());
+    }
+}
@@ -0,0 +1,134 @@
+use super::codec::{DeflateCodec, PayloadCodec};
+use anyhow::{Result, bail};
+
+/// Compresses the payload with deflate before it is embedded,
+/// marking it with [CompressionCodec::MAGIC] so it can be detected.
+///
+/// Besides shrinking text, this makes the embedded bytes look random.
+/// Incompressible input grows by a few bytes of deflate overhead.
+///
+/// # Error
+/// Returns an error if compression fails.
+pub fn compress_payload(data: &[u8]) -> Result<Vec<u8>> {
+    CompressionCodec::default().encode(data)
+}
+
+/// Decompresses a payload written by [compress_payload],
+/// up to [CompressionCodec::DEFAULT_MAX_SIZE] bytes.
+///
+/// # Error
+/// Returns an error if the data is not marked as compressed, is not valid deflate,
+/// which also covers truncated or corrupted payloads, or decompresses past the limit.
+pub fn decompress_payload(data: &[u8]) -> Result<Vec<u8>> {
+    CompressionCodec::default().decode(data)
+}
+
+/// Compresses payloads with [DeflateCodec] behind a magic marker,
+/// and refuses to decompress more than a maximum number of bytes.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct CompressionCodec {
+    max_size: usize,
+}
+
+impl Default for CompressionCodec {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_SIZE)
+    }
+}
+
+impl CompressionCodec {
+    /// The marker written in front of compressed payloads.
+    /// It starts with a byte that never begins valid UTF-8,
+    /// so it cannot be mistaken for the start of a text message.
+    pub const MAGIC: &'static [u8] = b"\x89PYZ";
+
+    /// The most bytes a payload may decompress to by default.
+    pub const DEFAULT_MAX_SIZE: usize = 64 * 1024 * 1024;
+
+    /// Creates a codec refusing to decompress more than `max_size` bytes.
+    pub fn new(max_size: usize) -> CompressionCodec {
+        Self { max_size }
+    }
+
+    /// Checks if the data starts with the compression marker.
+    pub fn is_compressed(&self, data: &[u8]) -> bool {
+        data.starts_with(Self::MAGIC)
+    }
+}
+
+impl PayloadCodec for CompressionCodec {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok([Self::MAGIC, &DeflateCodec.encode(data)?].concat())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match data.strip_prefix(Self::MAGIC) {
+            Some(compressed) => DeflateCodec.decode_with_limit(compressed, self.max_size),
+            None => bail!("Payload is not marked as compressed."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_round_trip() {
+        let data = b"secret ".repeat(100);
+        let compressed = compress_payload(&data).unwrap();
+
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress_payload(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compress_empty() {
+        let compressed = compress_payload(&[]).unwrap();
+        assert!(decompress_payload(&compressed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compress_incompressible() {
+        // A xorshift sequence stands in for already random data.
+        let mut state = 0x2545F491u32;
+        let data: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let compressed = compress_payload(&data).unwrap();
+        assert_eq!(decompress_payload(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_decompress_garbage() {
+        assert!(decompress_payload(b"definitely not compressed").is_err());
+        assert!(decompress_payload(&[]).is_err());
+        assert!(decompress_payload(CompressionCodec::MAGIC).is_err());
+
+        let compressed = compress_payload(b"secret message").unwrap();
+        assert!(decompress_payload(&compressed[..compressed.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_decompress_over_limit() {
+        let compressed = compress_payload(&[0; 4096]).unwrap();
+
+        assert!(CompressionCodec::new(4096).decode(&compressed).is_ok());
+        assert!(CompressionCodec::new(4095).decode(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_is_compressed() {
+        let codec = CompressionCodec::default();
+
+        assert!(codec.is_compressed(&compress_payload(b"secret").unwrap()));
+        assert!(!codec.is_compressed(b"secret"));
+        assert!(!codec.is_compressed(&[0x78, 0x9C, 0x03, 0x00]));
+    }
+}
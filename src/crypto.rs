@@ -0,0 +1,155 @@
+use super::codec::PayloadCodec;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Result, anyhow, bail};
+use argon2::Argon2;
+use std::fmt::{Debug, Error as FmtError, Formatter};
+
+/// Number of bytes of the random salt stored in front of the encrypted payload.
+const SALT_SIZE: usize = 16;
+
+/// Number of bytes of the random nonce stored after the salt.
+const NONCE_SIZE: usize = 12;
+
+/// Encrypts the data with AES-256-GCM, using a key derived from the passphrase with Argon2.
+///
+/// The output holds the random salt, then the random nonce, then the ciphertext
+/// followed by its authentication tag.
+///
+/// # Error
+/// Returns an error if the key cannot be derived or the encryption fails.
+pub fn encrypt_payload(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+
+    let cipher = cipher(passphrase, &salt)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, data)
+        .map_err(|_| anyhow!("Failed to encrypt the payload."))?;
+
+    let mut output = Vec::with_capacity(SALT_SIZE + NONCE_SIZE + ciphertext.len());
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend(ciphertext);
+
+    Ok(output)
+}
+
+/// Decrypts data written by [encrypt_payload] with the same passphrase.
+///
+/// # Error
+/// Returns an error if the data is too short, the passphrase is wrong
+/// or the data was tampered with.
+pub fn decrypt_payload(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if data.len() < SALT_SIZE + NONCE_SIZE {
+        bail!(
+            "Encrypted payload is too short. Expected at least {} bytes, found {}.",
+            SALT_SIZE + NONCE_SIZE,
+            data.len()
+        );
+    }
+
+    let (salt, rest) = data.split_at(SALT_SIZE);
+    let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+    cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the payload! Wrong password or corrupted data."))
+}
+
+/// Derives the AES-256 key from the passphrase and salt.
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the encryption key: {}", e))?;
+
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// Encrypts payloads with AES-256-GCM, see [encrypt_payload].
+///
+/// Its [Debug] output never shows the passphrase.
+#[derive(Clone)]
+pub struct AesGcmCodec {
+    passphrase: String,
+}
+
+impl Debug for AesGcmCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        f.debug_struct("AesGcmCodec")
+            .field("passphrase", &"<redacted>")
+            .finish()
+    }
+}
+
+impl AesGcmCodec {
+    /// Creates a codec encrypting with a key derived from the passphrase.
+    pub fn new(passphrase: &str) -> Self {
+        Self {
+            passphrase: passphrase.to_string(),
+        }
+    }
+}
+
+impl PayloadCodec for AesGcmCodec {
+    /// Encrypts the payload with [encrypt_payload].
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        encrypt_payload(data, &self.passphrase)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        decrypt_payload(data, &self.passphrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let encrypted = encrypt_payload(b"secret message", "hunter2").unwrap();
+
+        assert_eq!(encrypted.len(), SALT_SIZE + NONCE_SIZE + 14 + 16);
+        assert!(!encrypted.windows(6).any(|window| window == b"secret"));
+        assert_eq!(
+            decrypt_payload(&encrypted, "hunter2").unwrap(),
+            b"secret message"
+        );
+    }
+
+    #[test]
+    fn test_codec_debug_redacts_passphrase() {
+        let debug = format!("{:?}", AesGcmCodec::new("hunter2"));
+
+        assert!(!debug.contains("hunter2"));
+        assert_eq!(debug, "AesGcmCodec { passphrase: \"<redacted>\" }");
+    }
+
+    #[test]
+    fn test_decrypt_wrong_password() {
+        let encrypted = encrypt_payload(b"secret message", "hunter2").unwrap();
+        assert!(decrypt_payload(&encrypted, "hunter3").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tampered() {
+        let mut encrypted = encrypt_payload(b"secret message", "hunter2").unwrap();
+        *encrypted.last_mut().unwrap() ^= 0xFF;
+
+        assert!(decrypt_payload(&encrypted, "hunter2").is_err());
+        assert!(decrypt_payload(&encrypted[..10], "hunter2").is_err());
+    }
+
+    #[test]
+    fn test_aes_gcm_codec() {
+        let codec = AesGcmCodec::new("hunter2");
+        let encoded = codec.encode(b"secret").unwrap();
+
+        assert_ne!(codec.encode(b"secret").unwrap(), encoded);
+        assert_eq!(codec.decode(&encoded).unwrap(), b"secret");
+    }
+}
@@ -0,0 +1,72 @@
+use super::chunk_type::ChunkType;
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// The ways parsing a PNG, a chunk or a chunk type, or looking up a chunk, can fail.
+///
+/// Parsing functions returning [anyhow::Result] raise these as well,
+/// so they can be matched with `err.downcast_ref::<PngError>()`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum PngError {
+    /// The input does not start with the expected signature.
+    MissingSignature { expected: [u8; 8], found: Vec<u8> },
+
+    /// The input ends before the structure being read is complete.
+    ///
+    /// Both sizes are counted from the start of that structure, such as a whole chunk
+    /// or its 4-byte length field: `expected` is the number of bytes it needs and
+    /// `found` is the number of bytes left in the input.
+    TruncatedInput { expected: usize, found: usize },
+
+    /// The length stored in a chunk does not match its data.
+    LengthMismatch { declared: usize, found: usize },
+
+    /// The CRC stored in a chunk does not match the one computed from its contents.
+    CrcMismatch { expected: u32, found: u32 },
+
+    /// A chunk type is not made of four ASCII letters.
+    InvalidChunkType { chunk_type: Vec<u8> },
+
+    /// The input goes over one of the configured [crate::ParseLimits].
+    LimitExceeded { reason: String },
+
+    /// No chunk of the requested type is present.
+    ChunkNotFound { chunk_type: ChunkType },
+}
+
+impl Display for PngError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Self::MissingSignature { expected, found } => write!(
+                f,
+                "Invalid PNG signature.\nExpected: {:X?}\nFound: {:X?}",
+                expected, found
+            ),
+            Self::TruncatedInput { expected, found } => write!(
+                f,
+                "Input is truncated. Expected {} bytes, found {}.",
+                expected, found
+            ),
+            Self::LengthMismatch { declared, found } => write!(
+                f,
+                "Mismatched length: header says {}, but found {} bytes",
+                declared, found
+            ),
+            Self::CrcMismatch { expected, found } => write!(
+                f,
+                "CRC mismatched! Expected {:#010X}, found {:#010X}.",
+                expected, found
+            ),
+            Self::InvalidChunkType { chunk_type } => write!(
+                f,
+                "Invalid chunk type {:?}. Expected 4 ASCII letters.",
+                String::from_utf8_lossy(chunk_type)
+            ),
+            Self::LimitExceeded { reason } => write!(f, "{}", reason),
+            Self::ChunkNotFound { chunk_type } => {
+                write!(f, "Chunk of type '{}' not found.", chunk_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PngError {}
@@ -0,0 +1,88 @@
+use super::chunk::Chunk;
+use anyhow::{Error, Result, bail};
+
+/// The color type of an image, as recorded in its IHDR chunk.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ColorType {
+    Grayscale,
+    Rgb,
+    Palette,
+    GrayscaleAlpha,
+    Rgba,
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Grayscale,
+            2 => Self::Rgb,
+            3 => Self::Palette,
+            4 => Self::GrayscaleAlpha,
+            6 => Self::Rgba,
+            other => bail!("Unknown color type: {}", other),
+        })
+    }
+}
+
+/// The decoded contents of an IHDR chunk.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) struct ImageHeader {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) bit_depth: u8,
+    pub(crate) color_type: u8,
+    pub(crate) interlace_method: u8,
+}
+
+impl TryFrom<&Chunk> for ImageHeader {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if &chunk.chunk_type().bytes() != b"IHDR" {
+            bail!("Expected IHDR chunk, found '{}'.", chunk.chunk_type());
+        }
+
+        let data = chunk.data();
+        if data.len() < 13 {
+            bail!(
+                "Invalid IHDR chunk. Expected 13 bytes of data, found {}.",
+                data.len()
+            );
+        }
+
+        Ok(Self {
+            width: u32::from_be_bytes(data[0..4].try_into()?),
+            height: u32::from_be_bytes(data[4..8].try_into()?),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlace_method: data[12],
+        })
+    }
+}
+
+impl ImageHeader {
+    /// Returns the number of samples per pixel for the color type.
+    pub(crate) fn channels(&self) -> Result<usize> {
+        Ok(match ColorType::try_from(self.color_type)? {
+            ColorType::Grayscale | ColorType::Palette => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        })
+    }
+
+    /// Returns the number of bytes making up a single sample.
+    /// Bit depths below 8 are rounded up to a single byte.
+    pub(crate) fn bytes_per_sample(&self) -> usize {
+        (self.bit_depth as usize).div_ceil(8)
+    }
+
+    /// Returns the number of bytes in a single scanline,
+    /// excluding its leading filter-type byte.
+    pub(crate) fn scanline_len(&self) -> Result<usize> {
+        let bits = self.width as usize * self.channels()? * self.bit_depth as usize;
+        Ok(bits.div_ceil(8))
+    }
+}
@@ -59,10 +59,49 @@
 //!
 //! [PNG Struct Spec]: https://www.libpng.org/pub/png/spec/1.2/PNG-Structure.html
 
+#[macro_use]
+mod logging;
+
+mod background;
+mod capacity;
 mod chunk;
 mod chunk_type;
+mod codec;
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "encryption")]
+mod crypto;
+mod error;
+mod image_header;
+mod limits;
+mod normalize;
+mod payload;
 mod png;
+#[cfg(feature = "qr")]
+mod qr;
+mod raster;
+mod scan;
+mod size;
+pub mod stego;
+mod time;
+mod validate;
 
+pub use background::Background;
+pub use capacity::CapacityReport;
 pub use chunk::Chunk;
-pub use chunk_type::ChunkType;
-pub use png::Png;
+pub use chunk_type::{ChunkProperties, ChunkType};
+pub use codec::{ChainCodec, DeflateCodec, FramedCodec, IdentityCodec, PayloadCodec, TaggedCodec};
+#[cfg(feature = "compression")]
+pub use compression::{CompressionCodec, compress_payload, decompress_payload};
+#[cfg(feature = "encryption")]
+pub use crypto::{AesGcmCodec, decrypt_payload, encrypt_payload};
+pub use error::PngError;
+pub use image_header::ColorType;
+pub use limits::{ParseBudget, ParseLimits};
+pub use normalize::{NormalizeAction, NormalizeReport};
+pub use payload::ChunkPayload;
+pub use png::{KeepPolicy, Png, SerializeOptions};
+pub use scan::{LengthWarning, carve_png, find_png};
+pub use size::SizeBreakdown;
+pub use time::TimeFields;
+pub use validate::Issue;
@@ -0,0 +1,41 @@
+/// The [ParseBudget] struct caps the cumulative number of bytes
+/// allocated for chunk data and trailing bytes while parsing.
+///
+/// Guards against files made of many medium-sized chunks
+/// that each slip under per-chunk limits.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ParseBudget {
+    pub max_alloc_bytes: usize,
+}
+
+impl Default for ParseBudget {
+    fn default() -> Self {
+        Self {
+            max_alloc_bytes: usize::MAX,
+        }
+    }
+}
+
+/// The [ParseLimits] struct bounds the resources spent parsing untrusted input.
+/// Every limit is unbounded by default.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ParseLimits {
+    /// Maximum number of chunks to parse.
+    pub max_chunks: usize,
+
+    /// Maximum size of the input in bytes.
+    pub max_input_bytes: usize,
+
+    /// Cap on the cumulative allocation while parsing.
+    pub budget: ParseBudget,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_chunks: usize::MAX,
+            max_input_bytes: usize::MAX,
+            budget: ParseBudget::default(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Internal logging macros that forward to the `log` crate when the
+//! `log` feature is enabled, and compile to nothing otherwise.
+
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::png::Png;
+    use log::{Level, LevelFilter, Log, Metadata, Record};
+    use std::str::FromStr;
+    use std::sync::{Mutex, Once};
+
+    struct CapturingLogger;
+
+    static RECORDS: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+    static INIT: Once = Once::new();
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            RECORDS
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn init() {
+        INIT.call_once(|| {
+            log::set_logger(&CapturingLogger).unwrap();
+            log::set_max_level(LevelFilter::Trace);
+        });
+    }
+
+    #[test]
+    fn test_parse_emits_records() {
+        init();
+
+        let bytes = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"hey".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+        .as_bytes();
+        Png::try_from(bytes.as_slice()).unwrap();
+
+        let records = RECORDS.lock().unwrap();
+        assert!(records.iter().any(|(level, message)| *level == Level::Trace
+            && message == "Parsed 'RuSt' chunk of 3 bytes"));
+        assert!(
+            records
+                .iter()
+                .any(|(_, message)| message == "Verified CRC of 'RuSt' chunk")
+        );
+    }
+}
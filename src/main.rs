@@ -9,23 +9,109 @@ mod commands;
 use anyhow::Result;
 use args::{Cli, Commands};
 use clap::Parser;
+use commands::{DecodeOptions, EXIT_SUCCESS, EXIT_USAGE, EncodeOptions};
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            return match err.use_stderr() {
+                true => ExitCode::from(EXIT_USAGE),
+                false => ExitCode::from(EXIT_SUCCESS),
+            };
+        }
+    };
 
+    match run(&cli) {
+        Ok(()) => ExitCode::from(EXIT_SUCCESS),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(commands::exit_code(&err))
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
     match &cli.command {
         Commands::Encode {
             input,
             output,
             chunk_type,
             message,
-        } => commands::encode(input, output, chunk_type, message),
-        Commands::Decode { input, chunk_type } => commands::decode(input, chunk_type),
+            verify,
+            framed,
+            tagged,
+            compress,
+            password,
+            dry_run,
+            mode,
+        } => {
+            let options = EncodeOptions {
+                verify: *verify,
+                framed: *framed,
+                tagged: *tagged,
+                compress: *compress,
+                password: password.clone(),
+                dry_run: *dry_run,
+                mode: *mode,
+            };
+            commands::encode(input, output, chunk_type, message, &options)
+        }
+        Commands::Decode {
+            input,
+            chunk_type,
+            encoding,
+            framed,
+            tagged,
+            password,
+            on_invalid,
+        } => {
+            let options = DecodeOptions {
+                encoding: encoding.clone(),
+                framed: *framed,
+                tagged: *tagged,
+                password: password.clone(),
+                on_invalid: *on_invalid,
+            };
+            commands::decode(input, chunk_type, &options)
+        }
         Commands::Remove {
             input,
             output,
             chunk_type,
-        } => commands::remove(input, output, chunk_type),
-        Commands::Print { input } => commands::print(input),
+            dry_run,
+            mode,
+        } => commands::remove(input, output, chunk_type, *dry_run, *mode),
+        Commands::Strip {
+            input,
+            output,
+            trailer,
+            mode,
+        } => commands::strip(input, output, *trailer, *mode),
+        Commands::Print { input, json } => commands::print(input, *json),
+        Commands::List { input } => commands::list(input),
+        Commands::Map { input, width } => commands::map(input, *width),
+        Commands::Top { input, count } => commands::top(input, *count),
+        #[cfg(feature = "watch")]
+        Commands::Watch { input, chunk_type } => commands::watch(input, chunk_type),
+        Commands::Verify { input } => commands::verify(input),
+        Commands::Identify { input } => commands::identify(input),
+        Commands::ListTypes => commands::list_types(),
+        Commands::ExtractPng {
+            input,
+            output,
+            mode,
+        } => commands::extract_png(input, output, *mode),
+        #[cfg(feature = "image")]
+        Commands::Cover {
+            output,
+            width,
+            height,
+            noise,
+            color,
+            mode,
+        } => commands::cover(output, *width, *height, *noise, *color, *mode),
     }
 }
@@ -0,0 +1,87 @@
+use super::chunk_type::ChunkType;
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// A single change made while normalizing a PNG.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum NormalizeAction {
+    /// The CRC of the chunk at the given index was recomputed.
+    RepairedCrc { index: usize, chunk_type: ChunkType },
+
+    /// An ancillary chunk without any data was removed.
+    RemovedEmptyChunk { chunk_type: ChunkType },
+
+    /// The chunks were moved into canonical order.
+    Reordered,
+
+    /// A duplicate IEND chunk was removed.
+    RemovedDuplicateIend,
+
+    /// A missing IEND chunk was appended.
+    AddedIend,
+}
+
+impl Display for NormalizeAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Self::RepairedCrc { index, chunk_type } => {
+                write!(
+                    f,
+                    "Repaired CRC of '{}' chunk at index {}",
+                    chunk_type, index
+                )
+            }
+            Self::RemovedEmptyChunk { chunk_type } => {
+                write!(f, "Removed empty '{}' chunk", chunk_type)
+            }
+            Self::Reordered => write!(f, "Reordered chunks into canonical order"),
+            Self::RemovedDuplicateIend => write!(f, "Removed duplicate IEND chunk"),
+            Self::AddedIend => write!(f, "Added missing IEND chunk"),
+        }
+    }
+}
+
+/// The [NormalizeReport] struct lists every change made
+/// while verifying and normalizing a PNG.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct NormalizeReport {
+    actions: Vec<NormalizeAction>,
+}
+
+impl Display for NormalizeReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        for action in &self.actions {
+            writeln!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+impl NormalizeReport {
+    /// Returns the list of changes in the order they were made.
+    pub fn actions(&self) -> &[NormalizeAction] {
+        &self.actions
+    }
+
+    /// Checks if normalizing did not change anything.
+    pub fn is_unchanged(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, action: NormalizeAction) {
+        self.actions.push(action);
+    }
+}
+
+/// Returns the position of a chunk type in canonical order,
+/// or [None] if the specification does not constrain its position.
+pub(crate) fn canonical_rank(chunk_type: &ChunkType) -> Option<u8> {
+    match &chunk_type.bytes() {
+        b"IHDR" => Some(0),
+        b"cHRM" | b"gAMA" | b"iCCP" | b"sBIT" | b"sRGB" => Some(1),
+        b"PLTE" => Some(2),
+        b"bKGD" | b"hIST" | b"tRNS" | b"pHYs" | b"sPLT" => Some(3),
+        b"IDAT" => Some(4),
+        b"IEND" => Some(5),
+        _ => None,
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+
+/// The [ChunkPayload] trait describes how a type is
+/// stored as the data of a chunk and read back from it.
+pub trait ChunkPayload: Sized {
+    /// Serializes the payload into chunk data bytes.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Deserializes the payload from chunk data bytes.
+    fn from_bytes(bytes: &[u8]) -> Result<Self>;
+}
+
+impl ChunkPayload for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl ChunkPayload for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(str::from_utf8(bytes)?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::Png;
+    use anyhow::bail;
+    use std::str::FromStr;
+
+    #[derive(PartialEq, Debug)]
+    struct Coordinates {
+        x: u32,
+        y: u32,
+    }
+
+    impl ChunkPayload for Coordinates {
+        fn to_bytes(&self) -> Vec<u8> {
+            self.x
+                .to_be_bytes()
+                .iter()
+                .chain(self.y.to_be_bytes().iter())
+                .copied()
+                .collect()
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            if bytes.len() != 8 {
+                bail!("Expected 8 bytes, found {}.", bytes.len());
+            }
+
+            Ok(Self {
+                x: u32::from_be_bytes(bytes[0..4].try_into()?),
+                y: u32::from_be_bytes(bytes[4..8].try_into()?),
+            })
+        }
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let coordinates = Coordinates { x: 42, y: 1337 };
+
+        let mut png = Png::from_chunks(vec![]);
+        png.append_payload(chunk_type.clone(), &coordinates);
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let actual: Coordinates = png.read_payload(&chunk_type).unwrap();
+
+        assert_eq!(actual, coordinates);
+    }
+
+    #[test]
+    fn test_string_payload_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+
+        let mut png = Png::from_chunks(vec![]);
+        png.append_payload(chunk_type.clone(), &String::from("secret"));
+
+        let actual: String = png.read_payload(&chunk_type).unwrap();
+        assert_eq!(actual, "secret");
+    }
+
+    #[test]
+    fn test_read_missing_payload() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let png = Png::from_chunks(vec![]);
+
+        assert!(png.read_payload::<Vec<u8>>(&chunk_type).is_err());
+    }
+}
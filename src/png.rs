@@ -1,274 +1,4308 @@
+use super::capacity::CapacityReport;
 use super::chunk::Chunk;
 use super::chunk_type::ChunkType;
-use anyhow::{Error, Result, bail};
+use super::codec::{FramedCodec, PayloadCodec};
+use super::error::PngError;
+use super::image_header::ImageHeader;
+use super::limits::ParseLimits;
+use super::normalize::{NormalizeAction, NormalizeReport, canonical_rank};
+use super::payload::ChunkPayload;
+#[cfg(feature = "qr")]
+use super::qr;
+use super::scan::{LengthWarning, chunk_end_offset, find_png, iend_offset, length_warnings};
+use super::size::SizeBreakdown;
+use super::stego::{LsbOptions, lsb_capacity_with};
+#[cfg(feature = "clock")]
+use super::time::TimeFields;
+use super::validate::Issue;
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashSet;
 use std::fmt::{Display, Error as FmtError, Formatter};
+use std::io::{Read, Write};
+use std::ops::Range;
 use std::str::FromStr;
 
+/// Which chunk to keep when removing duplicates.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum KeepPolicy {
+    /// Keep the first chunk and remove later duplicates.
+    First,
+    /// Keep the last chunk and remove earlier duplicates.
+    Last,
+}
+
+/// Options controlling how [Png::to_bytes_with] serializes a PNG.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SerializeOptions {
+    /// Recompute the CRC of every chunk instead of writing the stored CRCs.
+    pub recompute_crc: bool,
+    /// Write the trailer following the IEND chunk.
+    pub include_trailer: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            recompute_crc: false,
+            include_trailer: true,
+        }
+    }
+}
+
 /// The [Png] struct represents a full PNG file
 /// along with its standard 8 bits header and sequence of chunks.
-#[derive(PartialEq, Eq, Clone, Debug)]
+///
+/// Well-formed chunks with a valid CRC following the IEND chunk are parsed as chunks,
+/// so messages written after IEND by older versions can still be read.
+/// Parsing stops at the first bytes after IEND that do not form such a chunk,
+/// and those are kept as the [trailer](Png::trailer) and written back as-is.
+///
+/// Two PNGs are equal when they hold the same signature, chunks and trailer.
+/// The [Png::set_recompute_crc_on_write] setting is not compared.
+#[derive(Eq, Clone, Debug)]
 pub struct Png {
     chunks: Vec<Chunk>,
+    trailer: Vec<u8>,
+    recompute_crc_on_write: bool,
+    signature: [u8; 8],
+}
+
+impl PartialEq for Png {
+    fn eq(&self, other: &Self) -> bool {
+        self.signature == other.signature
+            && self.chunks == other.chunks
+            && self.trailer == other.trailer
+    }
+}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = PngError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(bytes, &Self::STANDARD_HEADER, true, &ParseLimits::default())
+    }
+}
+
+impl Display for Png {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        for byte in self.as_bytes() {
+            write!(f, "{} ", byte)?;
+        }
+
+        Ok(())
+    }
 }
 
-impl TryFrom<&[u8]> for Png {
-    type Error = Error;
+impl Png {
+    pub(crate) const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+    /// Version of the framing written by [Png::append_message].
+    pub const MESSAGE_VERSION: u8 = 1;
+
+    /// How many missing part indices [Png::read_ordered_message] lists in its error.
+    const MISSING_PARTS_SHOWN: usize = 5;
+
+    /// The default width of [Png::ascii_map], matching a standard terminal.
+    pub const ASCII_MAP_WIDTH: usize = 80;
+
+    /// Creates a new [Png] instance from a vector of existing chunks.
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Self {
+            chunks,
+            trailer: Vec::new(),
+            recompute_crc_on_write: false,
+            signature: Self::STANDARD_HEADER,
+        }
+    }
+
+    /// Creates a new [Png] instance from raw bytes without verifying chunk CRCs.
+    ///
+    /// Chunks keep their stored CRCs, which can later be checked
+    /// and repaired using [Png::verify_and_normalize].
+    pub fn from_bytes_unverified(bytes: &[u8]) -> Result<Png, PngError> {
+        Self::parse(
+            bytes,
+            &Self::STANDARD_HEADER,
+            false,
+            &ParseLimits::default(),
+        )
+    }
+
+    /// Parses every PNG found in a stream of concatenated PNGs.
+    ///
+    /// Each PNG is located by its signature and parsed through its IEND chunk,
+    /// and any bytes in between PNGs are skipped.
+    ///
+    /// # Error
+    /// Returns an error if no PNG is found or any of the PNGs is invalid.
+    pub fn parse_all(bytes: &[u8]) -> Result<Vec<Png>> {
+        let mut pngs = Vec::<Png>::new();
+        let mut remaining = bytes;
+
+        while let Some(offset) = find_png(remaining) {
+            let end = offset + iend_offset(&remaining[offset..])?;
+            pngs.push(Png::try_from(&remaining[offset..end])?);
+            remaining = &remaining[end..];
+        }
+
+        if pngs.is_empty() {
+            bail!("No PNG signature found!");
+        }
+
+        Ok(pngs)
+    }
+
+    /// Walks the chunks in raw bytes without failing and reports every chunk
+    /// whose declared length overruns the buffer or does not match its CRC region.
+    ///
+    /// Useful for diagnosing where a manually edited file went wrong.
+    pub fn scan_length_consistency(bytes: &[u8]) -> Vec<LengthWarning> {
+        length_warnings(bytes)
+    }
+
+    /// Creates a new [Png] instance from raw bytes, aborting as soon as
+    /// any of the given limits is exceeded.
+    ///
+    /// # Error
+    /// Returns an error if the bytes are not a valid PNG or a limit is exceeded.
+    pub fn try_from_with_limits(bytes: &[u8], limits: &ParseLimits) -> Result<Png, PngError> {
+        Self::parse(bytes, &Self::STANDARD_HEADER, true, limits)
+    }
+
+    /// Creates a new [Png] instance by reading chunks from a stream one at a time,
+    /// without buffering the whole file first.
+    ///
+    /// Reading stops right after the IEND chunk, so any bytes following it
+    /// are left unread in the stream.
+    ///
+    /// Chunks longer than the 2^31-1 bytes allowed by the PNG specification
+    /// are rejected before any of their data is read.
+    ///
+    /// # Error
+    /// Returns an error if reading fails, the stream ends in the middle
+    /// of the signature or a chunk, or a chunk is invalid or too long.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Png> {
+        Self::from_reader_with_limits(reader, &ParseLimits::default())
+    }
+
+    /// Same as [Png::from_reader], but checks each chunk's declared length
+    /// against the given limits before reading its data.
+    ///
+    /// # Error
+    /// Returns an error if reading fails, the stream ends in the middle
+    /// of the signature or a chunk, a chunk is invalid or a limit is exceeded.
+    pub fn from_reader_with_limits<R: Read>(mut reader: R, limits: &ParseLimits) -> Result<Png> {
+        let limit_exceeded = |reason: String| PngError::LimitExceeded { reason };
+
+        let mut signature = Vec::with_capacity(8);
+        reader.by_ref().take(8).read_to_end(&mut signature)?;
+        if signature != Self::STANDARD_HEADER {
+            bail!(PngError::MissingSignature {
+                expected: Self::STANDARD_HEADER,
+                found: signature,
+            });
+        }
+
+        let mut chunks = Vec::<Chunk>::new();
+        let mut allocated = 0usize;
+        let mut total_read = Self::STANDARD_HEADER.len();
+        loop {
+            let mut chunk_bytes = Vec::with_capacity(12);
+            match reader.by_ref().take(4).read_to_end(&mut chunk_bytes)? {
+                0 => break,
+                4 => {}
+                read => bail!(PngError::TruncatedInput {
+                    expected: 4,
+                    found: read,
+                }),
+            }
+
+            let length = u32::from_be_bytes(chunk_bytes[..4].try_into()?) as u64;
+            if length as usize > CapacityReport::MAX_CHUNK_LENGTH {
+                bail!(limit_exceeded(format!(
+                    "Chunk length of {} bytes exceeds the PNG limit of {} bytes.",
+                    length,
+                    CapacityReport::MAX_CHUNK_LENGTH
+                )));
+            }
+
+            if chunks.len() >= limits.max_chunks {
+                bail!(limit_exceeded(format!(
+                    "PNG exceeds the limit of {} chunks.",
+                    limits.max_chunks
+                )));
+            }
+
+            total_read += 12 + length as usize;
+            if total_read > limits.max_input_bytes {
+                bail!(limit_exceeded(format!(
+                    "Input of at least {} bytes exceeds the limit of {} bytes.",
+                    total_read, limits.max_input_bytes
+                )));
+            }
+
+            allocated += length as usize;
+            if allocated > limits.budget.max_alloc_bytes {
+                bail!(limit_exceeded(format!(
+                    "Parse budget of {} bytes exceeded at chunk index {}.",
+                    limits.budget.max_alloc_bytes,
+                    chunks.len()
+                )));
+            }
+
+            let read = reader
+                .by_ref()
+                .take(length + 8)
+                .read_to_end(&mut chunk_bytes)?;
+            if read as u64 != length + 8 {
+                bail!(PngError::TruncatedInput {
+                    expected: length as usize + 12,
+                    found: read + 4,
+                });
+            }
+
+            let chunk = Chunk::try_from(chunk_bytes.as_slice())?;
+            trace!(
+                "Read '{}' chunk of {} bytes",
+                chunk.chunk_type(),
+                chunk.length()
+            );
+
+            let is_iend = &chunk.chunk_type().bytes() == b"IEND";
+            chunks.push(chunk);
+
+            if is_iend {
+                break;
+            }
+        }
+
+        debug!("Read PNG with {} chunks", chunks.len());
+        Ok(Self::from_chunks(chunks))
+    }
+
+    /// Creates a partial [Png] instance holding the chunks up to and including
+    /// the first chunk of the given type, without reading any further.
+    ///
+    /// Useful for cheaply reading the header and metadata of huge files.
+    /// The result has no trailer and, unless the stop type is IEND,
+    /// lacks the chunks following the stop chunk.
+    ///
+    /// # Error
+    /// Returns an error if the bytes end before a chunk of the given type
+    /// or IEND is found, or any of the chunks read is invalid.
+    pub fn parse_until(bytes: &[u8], stop_type: &ChunkType) -> Result<Png> {
+        let end = chunk_end_offset(bytes, &stop_type.bytes())?;
+        Ok(Self::parse(
+            &bytes[..end],
+            &Self::STANDARD_HEADER,
+            true,
+            &ParseLimits::default(),
+        )?)
+    }
+
+    /// Creates a new [Png] instance from raw bytes starting with a custom signature
+    /// instead of the PNG one, for chunk-based containers holding non-PNG data.
+    ///
+    /// The signature is kept and written back by [Png::as_bytes].
+    ///
+    /// # Error
+    /// Returns an error if the bytes do not start with the signature or any chunk is invalid.
+    pub fn try_from_with_signature(bytes: &[u8], signature: [u8; 8]) -> Result<Png, PngError> {
+        Self::parse(bytes, &signature, true, &ParseLimits::default())
+    }
+
+    fn parse(
+        bytes: &[u8],
+        signature: &[u8; 8],
+        verify_crc: bool,
+        limits: &ParseLimits,
+    ) -> Result<Self, PngError> {
+        let limit_exceeded = |reason: String| PngError::LimitExceeded { reason };
+
+        if bytes.len() > limits.max_input_bytes {
+            return Err(limit_exceeded(format!(
+                "Input of {} bytes exceeds the limit of {} bytes.",
+                bytes.len(),
+                limits.max_input_bytes
+            )));
+        }
+
+        if !bytes.starts_with(signature) {
+            return Err(PngError::MissingSignature {
+                expected: *signature,
+                found: bytes[..bytes.len().min(8)].to_vec(),
+            });
+        }
+
+        let mut chunks = Vec::<Chunk>::new();
+        let mut allocated = 0usize;
+
+        let mut seen_iend = false;
+        let mut i = 8;
+        while i < bytes.len() {
+            // Older versions wrote messages after IEND, so keep reading chunks
+            // there for as long as they are well-formed and pass their CRC.
+            if seen_iend && Self::valid_chunk_size(&bytes[i..]).is_none() {
+                break;
+            }
+
+            let Some(length) = bytes[i..].first_chunk::<4>() else {
+                return Err(PngError::TruncatedInput {
+                    expected: 4,
+                    found: bytes.len() - i,
+                });
+            };
+
+            let length = u32::from_be_bytes(*length) as usize;
+            let total_chunk_size = 12 + length;
+
+            if i + total_chunk_size > bytes.len() {
+                return Err(PngError::TruncatedInput {
+                    expected: total_chunk_size,
+                    found: bytes.len() - i,
+                });
+            }
+
+            if chunks.len() >= limits.max_chunks {
+                return Err(limit_exceeded(format!(
+                    "PNG exceeds the limit of {} chunks.",
+                    limits.max_chunks
+                )));
+            }
+
+            allocated += length;
+            if allocated > limits.budget.max_alloc_bytes {
+                return Err(limit_exceeded(format!(
+                    "Parse budget of {} bytes exceeded at chunk index {}.",
+                    limits.budget.max_alloc_bytes,
+                    chunks.len()
+                )));
+            }
+
+            let chunk_bytes = &bytes[i..(i + total_chunk_size)];
+            let chunk = match verify_crc {
+                true => Chunk::try_from(chunk_bytes)?,
+                false => Chunk::from_bytes_unverified(chunk_bytes)?,
+            };
+            trace!(
+                "Parsed '{}' chunk of {} bytes",
+                chunk.chunk_type(),
+                chunk.length()
+            );
+            if verify_crc {
+                trace!("Verified CRC of '{}' chunk", chunk.chunk_type());
+            }
+            i += 12 + length;
+
+            seen_iend |= &chunk.chunk_type().bytes() == b"IEND";
+            chunks.push(chunk);
+        }
+
+        if allocated + (bytes.len() - i) > limits.budget.max_alloc_bytes {
+            return Err(limit_exceeded(format!(
+                "Parse budget of {} bytes exceeded by trailing bytes.",
+                limits.budget.max_alloc_bytes
+            )));
+        }
+
+        debug!(
+            "Parsed PNG with {} chunks and {} trailing bytes",
+            chunks.len(),
+            bytes.len() - i
+        );
+
+        Ok(Self {
+            chunks,
+            trailer: bytes[i..].to_vec(),
+            recompute_crc_on_write: false,
+            signature: *signature,
+        })
+    }
+
+    /// Returns the size of the well-formed chunk with a valid CRC
+    /// at the start of the bytes, if there is one.
+    fn valid_chunk_size(bytes: &[u8]) -> Option<usize> {
+        let length = u32::from_be_bytes(*bytes.first_chunk::<4>()?) as usize;
+        let chunk_bytes = bytes.get(..length.checked_add(12)?)?;
+        Chunk::try_from(chunk_bytes).ok()?;
+        Some(chunk_bytes.len())
+    }
+
+    /// Appends a new chunk to existing list of chunks.
+    ///
+    /// If an IEND chunk is present, the new chunk is placed
+    /// right before it so that IEND remains the last chunk.
+    /// Chunks already following IEND, as written by older versions, are left in place.
+    /// Use [Png::insert_chunk] to place a chunk at a specific index instead.
+    pub fn append_chunk(&mut self, chunk: Chunk) {
+        debug!(
+            "Appending '{}' chunk of {} bytes",
+            chunk.chunk_type(),
+            chunk.length()
+        );
+
+        match self
+            .chunks
+            .iter()
+            .rposition(|c| &c.chunk_type().bytes() == b"IEND")
+        {
+            Some(i) => self.chunks.insert(i, chunk),
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    /// Inserts a chunk at the given index, shifting later chunks back.
+    ///
+    /// # Error
+    /// Returns an error if the index is past the end of the chunks
+    /// or the chunk would not leave IEND as the last chunk.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        if index > self.chunks.len() {
+            bail!(
+                "Chunk index {} is out of bounds for {} chunks.",
+                index,
+                self.chunks.len()
+            );
+        }
+
+        let iend = self
+            .chunks
+            .iter()
+            .position(|c| &c.chunk_type().bytes() == b"IEND");
+        let inserting_iend = &chunk.chunk_type().bytes() == b"IEND";
+
+        match iend {
+            Some(_) if inserting_iend => bail!("PNG already has an IEND chunk."),
+            Some(iend) if index > iend => bail!("Cannot insert a chunk after IEND."),
+            None if inserting_iend && index != self.chunks.len() => {
+                bail!("IEND chunk must be the last chunk.")
+            }
+            _ => {}
+        }
+
+        debug!(
+            "Inserting '{}' chunk at index {}",
+            chunk.chunk_type(),
+            index
+        );
+        self.chunks.insert(index, chunk);
+        Ok(())
+    }
+
+    /// Appends a new chunk the same way as [Png::append_chunk],
+    /// after checking that it follows the PNG chunk rules.
+    ///
+    /// # Error
+    /// Returns an error if the chunk type is invalid or has its reserved bit set,
+    /// the chunk would duplicate an IHDR, PLTE or IEND chunk,
+    /// or the PNG has no IEND chunk to append before.
+    pub fn append_chunk_checked(&mut self, chunk: Chunk) -> Result<()> {
+        let chunk_type = chunk.chunk_type();
+        if !chunk_type.is_valid() {
+            bail!("Invalid chunk type: {}", chunk_type);
+        }
+
+        if matches!(&chunk_type.bytes(), b"IHDR" | b"PLTE" | b"IEND")
+            && self.chunks.iter().any(|c| c.chunk_type() == chunk_type)
+        {
+            bail!("Only a single '{}' chunk is allowed.", chunk_type);
+        }
+
+        if !self
+            .chunks
+            .iter()
+            .any(|c| &c.chunk_type().bytes() == b"IEND")
+        {
+            bail!("Cannot append to a PNG without an IEND chunk.");
+        }
+
+        self.append_chunk(chunk);
+        Ok(())
+    }
+
+    /// Appends the chunk the same way as [Png::append_chunk], unless a chunk with the
+    /// same type and data is already present, so embedding twice does not duplicate it.
+    ///
+    /// Returns whether the chunk was added.
+    pub fn append_chunk_if_absent(&mut self, chunk: Chunk) -> bool {
+        let present = self
+            .chunks
+            .iter()
+            .any(|c| c.chunk_type() == chunk.chunk_type() && c.data() == chunk.data());
+
+        if !present {
+            self.append_chunk(chunk);
+        }
+        !present
+    }
+
+    /// Builds a chunk from a chunk type string and data, then appends it
+    /// the same way as [Png::append_chunk].
+    ///
+    /// # Error
+    /// Returns an error if the chunk type string is invalid.
+    pub fn append_raw(&mut self, type_str: &str, data: Vec<u8>) -> Result<()> {
+        let chunk_type = ChunkType::from_str(type_str)?;
+        self.append_chunk(Chunk::new(chunk_type, data));
+        Ok(())
+    }
+
+    /// Inserts a chunk right after the last IDAT chunk, so it sits between
+    /// the image data and the IEND chunk.
+    ///
+    /// # Error
+    /// Returns an error if there is no IDAT or IEND chunk,
+    /// or the IEND chunk does not follow the last IDAT chunk.
+    pub fn insert_between_idat_and_iend(&mut self, chunk: Chunk) -> Result<()> {
+        let last_idat = match self
+            .chunks
+            .iter()
+            .rposition(|c| &c.chunk_type().bytes() == b"IDAT")
+        {
+            Some(i) => i,
+            None => bail!("No IDAT chunk found!"),
+        };
+
+        let iend = match self
+            .chunks
+            .iter()
+            .position(|c| &c.chunk_type().bytes() == b"IEND")
+        {
+            Some(i) => i,
+            None => bail!("No IEND chunk found!"),
+        };
+
+        if iend < last_idat {
+            bail!("IEND chunk found before the last IDAT chunk!");
+        }
+
+        self.chunks.insert(last_idat + 1, chunk);
+        Ok(())
+    }
+
+    /// Stamps the PNG with the current UTC time, like an image editor does on save.
+    ///
+    /// Replaces the first tIME chunk in place, removing any others,
+    /// or inserts a new one before the IEND chunk if none exists.
+    #[cfg(feature = "clock")]
+    pub fn touch(&mut self) -> Result<()> {
+        let now = TimeFields::try_from(chrono::Utc::now().naive_utc())?;
+        let chunk = Chunk::new_time(
+            now.year, now.month, now.day, now.hour, now.minute, now.second,
+        )?;
+
+        let mut replaced = false;
+        self.chunks.retain_mut(|c| {
+            if &c.chunk_type().bytes() != b"tIME" {
+                return true;
+            }
+            if replaced {
+                return false;
+            }
+            *c = chunk.clone();
+            replaced = true;
+            true
+        });
+
+        if !replaced {
+            self.append_chunk(chunk);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a new [Png] with the chunk appended, leaving the original unchanged.
+    ///
+    /// Note that this clones every existing chunk along with its data,
+    /// so it can be expensive for large images.
+    pub fn clone_with_chunk(&self, chunk: Chunk) -> Png {
+        let mut png = self.clone();
+        png.append_chunk(chunk);
+        png
+    }
+
+    /// Serializes the payload and appends it as a new chunk of the given type.
+    pub fn append_payload<T: ChunkPayload>(&mut self, chunk_type: ChunkType, payload: &T) {
+        self.append_chunk(Chunk::new(chunk_type, payload.to_bytes()));
+    }
+
+    /// Reads the payload stored in the first chunk of the given type.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found
+    /// or its data cannot be deserialized into the payload.
+    pub fn read_payload<T: ChunkPayload>(&self, chunk_type: &ChunkType) -> Result<T> {
+        let (_, chunk) = self.find_chunk(chunk_type)?;
+
+        T::from_bytes(chunk.data())
+    }
+
+    /// Encodes the data with the codec and appends it as a new chunk of the given type.
+    ///
+    /// # Error
+    /// Returns an error if the codec fails to encode the data.
+    pub fn append_with_codec(
+        &mut self,
+        chunk_type: ChunkType,
+        data: &[u8],
+        codec: &dyn PayloadCodec,
+    ) -> Result<()> {
+        self.append_chunk(Chunk::new(chunk_type, codec.encode(data)?));
+        Ok(())
+    }
+
+    /// Decodes the data of the first chunk of the given type with the codec.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found
+    /// or the codec fails to decode its data.
+    pub fn read_with_codec(
+        &self,
+        chunk_type: &ChunkType,
+        codec: &dyn PayloadCodec,
+    ) -> Result<Vec<u8>> {
+        let (_, chunk) = self.find_chunk(chunk_type)?;
+
+        codec.decode(chunk.data())
+    }
+
+    /// Appends a message as a new chunk of the given type, framed with
+    /// a 1-byte version followed by its length as a 4-byte big-endian integer.
+    ///
+    /// # Error
+    /// Returns an error if the message is too long for its length to fit in 4 bytes.
+    pub fn append_message(&mut self, chunk_type: ChunkType, message: &[u8]) -> Result<()> {
+        let mut data = vec![Self::MESSAGE_VERSION];
+        data.extend(FramedCodec.encode(message)?);
+
+        self.append_chunk(Chunk::new(chunk_type, data));
+        Ok(())
+    }
+
+    /// Reads a message written by [Png::append_message] from the first chunk of the given type.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found,
+    /// the framing version is not supported or the framing is malformed.
+    pub fn read_message(&self, chunk_type: &ChunkType) -> Result<Vec<u8>> {
+        let (_, chunk) = self.find_chunk(chunk_type)?;
+
+        match chunk.data().split_first() {
+            Some((&Self::MESSAGE_VERSION, framed)) => FramedCodec.decode(framed),
+            Some((version, _)) => bail!(
+                "Unsupported message version {}. Expected version {}.",
+                version,
+                Self::MESSAGE_VERSION
+            ),
+            None => bail!("Message chunk is empty."),
+        }
+    }
+
+    /// Appends a short message as a new chunk of the given type, encoded as
+    /// a QR byte stream with the highest level of error correction.
+    ///
+    /// The message remains readable with [Png::read_qr_message] as long as no
+    /// block of the stream has more corrupted bytes than half of its error
+    /// correction codewords, which is about 30% of the block. Corruption is
+    /// counted per block, so a burst within one block can fail sooner.
+    ///
+    /// # Error
+    /// Returns an error if the message is longer than 98 bytes.
+    #[cfg(feature = "qr")]
+    pub fn embed_qr_message(&mut self, chunk_type: ChunkType, message: &str) -> Result<()> {
+        let data = qr::encode(message.as_bytes())?;
+        self.append_chunk(Chunk::new(chunk_type, data));
+
+        Ok(())
+    }
+
+    /// Reads a message written by [Png::embed_qr_message] from the first chunk of the given type,
+    /// correcting any errors its error correction codewords allow.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found, its data is too
+    /// corrupted to be corrected or the message is not valid UTF-8.
+    #[cfg(feature = "qr")]
+    pub fn read_qr_message(&self, chunk_type: &ChunkType) -> Result<String> {
+        let (_, chunk) = self.find_chunk(chunk_type)?;
+
+        Ok(String::from_utf8(qr::decode(chunk.data())?)?)
+    }
+
+    /// Appends bytes to the data of the first chunk of the given type,
+    /// updating its length and CRC, instead of adding a new chunk.
+    ///
+    /// Note that this may make the chunk's content invalid for its declared type,
+    /// such as a tEXt chunk whose text no longer decodes as Latin-1.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found
+    /// or it is an IHDR, PLTE, IDAT or IEND chunk.
+    pub fn append_to_chunk_data(&mut self, chunk_type: &ChunkType, extra: &[u8]) -> Result<()> {
+        if ChunkType::STANDARD_CRITICAL.contains(chunk_type) {
+            bail!("Cannot append to the data of '{}' chunks.", chunk_type);
+        }
+
+        let (index, _) = self.find_chunk(chunk_type)?;
+        let chunk = &mut self.chunks[index];
+
+        let mut data = chunk.data().to_vec();
+        data.extend_from_slice(extra);
+        chunk.set_data(data);
+
+        Ok(())
+    }
+
+    /// Returns the stored CRC of the first chunk of the given type,
+    /// which is a cheap way to tell whether the chunk changed between two files.
+    pub fn crc_of_first(&self, chunk_type: &ChunkType) -> Option<u32> {
+        self.chunks
+            .iter()
+            .find(|c| c.chunk_type() == chunk_type)
+            .map(|c| c.crc())
+    }
+
+    /// Writes the data of every chunk of the given type to the writer,
+    /// concatenated in file order, without copying it first.
+    ///
+    /// # Error
+    /// Returns an error if no chunk of requested type is found or writing fails.
+    pub fn write_chunk_data<W: Write>(&self, chunk_type: &ChunkType, mut writer: W) -> Result<()> {
+        let chunks = self.chunks_by_type(chunk_type);
+        if chunks.is_empty() {
+            bail!(PngError::ChunkNotFound {
+                chunk_type: chunk_type.clone()
+            });
+        }
+
+        for chunk in chunks {
+            writer.write_all(chunk.data())?;
+        }
+        Ok(())
+    }
+
+    /// Splits a message into parts of at most `part_size` bytes and appends each
+    /// as a new chunk of the given type, prefixed with its index and the total
+    /// number of parts as 4-byte big-endian integers.
+    ///
+    /// # Error
+    /// Returns an error if the part size is zero.
+    pub fn append_ordered_message(
+        &mut self,
+        chunk_type: ChunkType,
+        message: &[u8],
+        part_size: usize,
+    ) -> Result<()> {
+        if part_size == 0 {
+            bail!("Part size must be at least 1 byte.");
+        }
+
+        let parts: Vec<&[u8]> = match message.is_empty() {
+            true => vec![message],
+            false => message.chunks(part_size).collect(),
+        };
+
+        let total = parts.len() as u32;
+        for (index, part) in parts.into_iter().enumerate() {
+            let mut data = Vec::with_capacity(8 + part.len());
+            data.extend_from_slice(&(index as u32).to_be_bytes());
+            data.extend_from_slice(&total.to_be_bytes());
+            data.extend_from_slice(part);
+
+            self.append_chunk(Chunk::new(chunk_type.clone(), data));
+        }
+        Ok(())
+    }
+
+    /// Reassembles a message written by [Png::append_ordered_message]
+    /// from the chunks of the given type, in any order.
+    ///
+    /// # Error
+    /// Returns an error if no chunk of requested type is found, a part header
+    /// is malformed or inconsistent, two parts share an index but not their data,
+    /// or parts are missing, giving their count and the first few indices.
+    pub fn read_ordered_message(&self, chunk_type: &ChunkType) -> Result<Vec<u8>> {
+        let mut parts = Vec::<(u32, &[u8])>::new();
+        let mut total = None;
+
+        for chunk in self.chunks_by_type(chunk_type) {
+            let data = chunk.data();
+            if data.len() < 8 {
+                bail!("Ordered message part is missing its 8 byte header.");
+            }
+
+            let index = u32::from_be_bytes(data[0..4].try_into()?);
+            let count = u32::from_be_bytes(data[4..8].try_into()?);
+            if *total.get_or_insert(count) != count {
+                bail!("Ordered message parts disagree on the number of parts.");
+            }
+            if index >= count {
+                bail!("Part index {} is out of range for {} parts.", index, count);
+            }
+
+            parts.push((index, &data[8..]));
+        }
+
+        let total = total.ok_or_else(|| PngError::ChunkNotFound {
+            chunk_type: chunk_type.clone(),
+        })?;
+
+        parts.sort_by_key(|(index, _)| *index);
+        if let Some(pair) = parts
+            .windows(2)
+            .find(|pair| pair[0].0 == pair[1].0 && pair[0].1 != pair[1].1)
+        {
+            bail!(
+                "Ordered message has conflicting data for part {}.",
+                pair[0].0
+            );
+        }
+        parts.dedup_by_key(|(index, _)| *index);
+
+        // The count comes from the file, so only the gaps between the parts present
+        // are walked, and only the first few missing indices are listed.
+        let missing_count = total as usize - parts.len();
+        if missing_count > 0 {
+            let mut next = 0;
+            let mut missing: Vec<String> = parts
+                .iter()
+                .map(|(index, _)| *index as u64)
+                .chain([total as u64])
+                .flat_map(|index| {
+                    let gap = next..index;
+                    next = index + 1;
+                    gap
+                })
+                .take(Self::MISSING_PARTS_SHOWN)
+                .map(|index| index.to_string())
+                .collect();
+            if missing_count > missing.len() {
+                missing.push("...".to_string());
+            }
+
+            bail!(
+                "Ordered message is incomplete. Missing {} of {} parts: {}.",
+                missing_count,
+                total,
+                missing.join(", ")
+            );
+        }
+
+        Ok(parts
+            .into_iter()
+            .flat_map(|(_, part)| part.to_vec())
+            .collect())
+    }
+
+    /// Returns a list of all the chunks of a specific chunk type.
+    pub fn chunks_by_type(&self, chunk_type: &ChunkType) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|c| c.chunk_type() == chunk_type)
+            .collect()
+    }
+
+    /// Returns the first chunk of the given type along with its index.
+    fn find_chunk(&self, chunk_type: &ChunkType) -> Result<(usize, &Chunk)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.chunk_type() == chunk_type)
+            .ok_or_else(|| Self::chunk_not_found(chunk_type))
+    }
+
+    /// Returns the last chunk of the given type along with its index.
+    fn find_last_chunk(&self, chunk_type: &ChunkType) -> Result<(usize, &Chunk)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .rfind(|(_, c)| c.chunk_type() == chunk_type)
+            .ok_or_else(|| Self::chunk_not_found(chunk_type))
+    }
+
+    fn chunk_not_found(chunk_type: &ChunkType) -> anyhow::Error {
+        PngError::ChunkNotFound {
+            chunk_type: chunk_type.clone(),
+        }
+        .into()
+    }
+
+    /// Removes the first matching chunk of a particular type.
+    ///
+    /// # Error
+    /// Returns an error if there are no chunks or
+    /// the chunk of requested type is not found.
+    pub fn remove_first_chunk(&mut self, chunk_type: &ChunkType) -> Result<Chunk> {
+        if self.chunks.is_empty() {
+            bail!("No chunks to remove from!");
+        }
+
+        let (index, _) = self.find_chunk(chunk_type)?;
+
+        debug!("Removing '{}' chunk at index {}", chunk_type, index);
+        Ok(self.chunks.remove(index))
+    }
+
+    /// Replaces the first matching chunk of a particular type with the new chunk,
+    /// keeping its position, unlike removing it and appending the new one.
+    ///
+    /// The new chunk must have the same type, so replacing cannot
+    /// move, duplicate or remove an IHDR or IEND chunk.
+    ///
+    /// # Error
+    /// Returns an error if the new chunk has a different type
+    /// or no chunk of the requested type is found.
+    pub fn replace_first_chunk(&mut self, chunk_type: &ChunkType, new_chunk: Chunk) -> Result<()> {
+        if new_chunk.chunk_type() != chunk_type {
+            bail!(
+                "Cannot replace a '{}' chunk with a '{}' chunk.",
+                chunk_type,
+                new_chunk.chunk_type()
+            );
+        }
+
+        let (index, _) = self.find_chunk(chunk_type)?;
+        let chunk = &mut self.chunks[index];
+
+        *chunk = new_chunk;
+        Ok(())
+    }
+
+    /// Removes the last matching chunk of a particular type.
+    ///
+    /// # Error
+    /// Returns an error if there are no chunks or
+    /// the chunk of requested type is not found.
+    pub fn remove_last_chunk(&mut self, chunk_type: &ChunkType) -> Result<Chunk> {
+        if self.chunks.is_empty() {
+            bail!("No chunks to remove from!");
+        }
+
+        let (index, _) = self.find_last_chunk(chunk_type)?;
+
+        debug!("Removing '{}' chunk at index {}", chunk_type, index);
+        Ok(self.chunks.remove(index))
+    }
+
+    /// Removes every chunk of a particular type and returns how many were removed.
+    ///
+    /// IHDR, PLTE, IDAT and IEND chunks are required to display the image,
+    /// so asking to remove them is a no-op that returns 0.
+    pub fn remove_all_chunks(&mut self, chunk_type: &ChunkType) -> usize {
+        if ChunkType::STANDARD_CRITICAL.contains(chunk_type) {
+            return 0;
+        }
+
+        let original_len = self.chunks.len();
+        self.chunks.retain(|c| c.chunk_type() != chunk_type);
+
+        let removed = original_len - self.chunks.len();
+        debug!("Removed {} '{}' chunks", removed, chunk_type);
+        removed
+    }
+
+    /// Changes the type of every chunk of a particular type and returns how many were renamed.
+    ///
+    /// Note that the case of each letter encodes the chunk's property flags,
+    /// so renaming may change how decoders treat the chunk.
+    /// Use [Png::rename_chunks_preserving_semantics] to keep them intact.
+    ///
+    /// # Error
+    /// Returns an error if either type is IHDR, PLTE, IDAT or IEND.
+    pub fn rename_chunks(&mut self, from: &ChunkType, to: ChunkType) -> Result<usize> {
+        for chunk_type in [from, &to] {
+            if ChunkType::STANDARD_CRITICAL.contains(chunk_type) {
+                bail!("Cannot rename '{}' chunks.", chunk_type);
+            }
+        }
+
+        let mut renamed = 0;
+        for chunk in self.chunks.iter_mut() {
+            if chunk.chunk_type() == from {
+                *chunk = Chunk::new(to.clone(), chunk.data().to_vec());
+                renamed += 1;
+            }
+        }
+
+        debug!("Renamed {} '{}' chunks to '{}'", renamed, from, to);
+        Ok(renamed)
+    }
+
+    /// Renames every chunk of a particular type to the given four letters,
+    /// adjusting their case so the chunks keep their critical, public,
+    /// reserved and safe-to-copy flags.
+    ///
+    /// # Error
+    /// Returns an error if the base is not four ASCII letters,
+    /// or either type is IHDR, PLTE, IDAT or IEND.
+    pub fn rename_chunks_preserving_semantics(
+        &mut self,
+        from: &ChunkType,
+        base: &str,
+    ) -> Result<usize> {
+        let to = ChunkType::from_str(base)?.with_properties(from.properties());
+        self.rename_chunks(from, to)
+    }
+
+    /// Swaps the chunks at the given indices.
+    ///
+    /// # Error
+    /// Returns an error if either index is out of bounds
+    /// or either chunk is IHDR or IEND, which must stay in position.
+    /// Use [Png::force_swap_chunks] to move them anyway.
+    pub fn swap_chunks(&mut self, i: usize, j: usize) -> Result<()> {
+        self.check_chunk_index(i)?;
+        self.check_chunk_index(j)?;
+
+        if i != j {
+            for chunk in [&self.chunks[i], &self.chunks[j]] {
+                if matches!(&chunk.chunk_type().bytes(), b"IHDR" | b"IEND") {
+                    bail!(
+                        "Refusing to move '{}' chunk out of position.",
+                        chunk.chunk_type()
+                    );
+                }
+            }
+        }
+
+        self.chunks.swap(i, j);
+        Ok(())
+    }
+
+    /// Swaps the chunks at the given indices, even if either one is IHDR or IEND.
+    ///
+    /// # Error
+    /// Returns an error if either index is out of bounds.
+    pub fn force_swap_chunks(&mut self, i: usize, j: usize) -> Result<()> {
+        self.check_chunk_index(i)?;
+        self.check_chunk_index(j)?;
+
+        self.chunks.swap(i, j);
+        Ok(())
+    }
+
+    fn check_chunk_index(&self, index: usize) -> Result<()> {
+        if index >= self.chunks.len() {
+            bail!(
+                "Chunk index {} is out of bounds for {} chunks.",
+                index,
+                self.chunks.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the 8 bytes signature written at the start of the file,
+    /// which is the standard PNG signature unless changed by [Png::set_signature].
+    pub fn header(&self) -> &[u8; 8] {
+        &self.signature
+    }
+
+    /// Replaces the signature written at the start of the file.
+    ///
+    /// Useful for chunk-based containers of non-PNG data. Such files
+    /// can be read back with [Png::try_from_with_signature].
+    pub fn set_signature(&mut self, signature: [u8; 8]) {
+        self.signature = signature;
+    }
+
+    /// Returns a slice of existing chunks in file order, including IHDR and IEND.
+    pub fn chunks(&self) -> &[Chunk] {
+        self.chunks.as_slice()
+    }
+
+    /// Returns the first chunk, which should be IHDR in a valid file.
+    pub fn first_chunk(&self) -> Option<&Chunk> {
+        self.chunks.first()
+    }
+
+    /// Returns the last chunk, which should be IEND in a valid file.
+    pub fn last_chunk(&self) -> Option<&Chunk> {
+        self.chunks.last()
+    }
+
+    /// Returns the chunk at the given position in file order, if any.
+    pub fn get_chunk(&self, index: usize) -> Option<&Chunk> {
+        self.chunks.get(index)
+    }
+
+    /// Returns mutable access to the chunk at the given position in file order, if any.
+    ///
+    /// The [Chunk] mutators keep its length and CRC consistent with its data.
+    pub fn chunk_at_mut(&mut self, index: usize) -> Option<&mut Chunk> {
+        self.chunks.get_mut(index)
+    }
+
+    /// Returns the number of chunks, including IHDR and IEND.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns true if the PNG holds nothing beyond the chunks needed to display the image,
+    /// that is every chunk is one of IHDR, PLTE, IDAT or IEND.
+    ///
+    /// Any other chunk, whether a standard ancillary chunk such as tEXt or a custom one,
+    /// makes the PNG non-empty. The trailer is not taken into account.
+    pub fn is_empty(&self) -> bool {
+        self.chunks
+            .iter()
+            .all(|chunk| ChunkType::STANDARD_CRITICAL.contains(chunk.chunk_type()))
+    }
+
+    /// Returns the byte range within the serialized file where the data of each chunk lives,
+    /// excluding its length, type and CRC fields.
+    pub fn data_ranges(&self) -> Vec<(ChunkType, Range<usize>)> {
+        let mut offset = Self::STANDARD_HEADER.len();
+
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                let start = offset + 8;
+                let end = start + chunk.data().len();
+                offset = end + 4;
+
+                (chunk.chunk_type().clone(), start..end)
+            })
+            .collect()
+    }
+
+    /// Get the first matching chunk of a particular type.
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        let chunk_type = match ChunkType::from_str(chunk_type) {
+            Ok(chunk_type) => chunk_type,
+            Err(_) => return None,
+        };
+
+        self.chunks
+            .iter()
+            .find(|&chunk| chunk_type == *chunk.chunk_type())
+            .map(|v| v as _)
+    }
+
+    /// Verifies and normalizes the PNG in one go, returning a report of every change.
+    ///
+    /// This checks the CRC of every chunk (recomputing mismatched ones only
+    /// if `repair_crc` is set), removes ancillary chunks without data,
+    /// moves chunks into canonical order and ensures exactly one IEND chunk
+    /// is present at the end. Chunks whose position is not constrained by the
+    /// specification stay right after the chunk they originally followed.
+    ///
+    /// # Error
+    /// Returns an error, leaving the PNG untouched, if a CRC mismatch
+    /// is found and `repair_crc` is not set.
+    pub fn verify_and_normalize(&mut self, repair_crc: bool) -> Result<NormalizeReport> {
+        let invalid_crcs: Vec<usize> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| !chunk.has_valid_crc())
+            .map(|(i, _)| i)
+            .collect();
+
+        if !invalid_crcs.is_empty() && !repair_crc {
+            bail!("CRC mismatched for chunk(s) at index: {:?}", invalid_crcs);
+        }
+
+        let mut report = NormalizeReport::default();
+
+        for index in invalid_crcs {
+            let chunk = &mut self.chunks[index];
+            chunk.repair_crc();
+            report.push(NormalizeAction::RepairedCrc {
+                index,
+                chunk_type: chunk.chunk_type().clone(),
+            });
+        }
+
+        let mut removed = Vec::<ChunkType>::new();
+        self.chunks.retain(|chunk| {
+            let is_empty_ancillary = chunk.data().is_empty() && !chunk.chunk_type().is_critical();
+            if is_empty_ancillary {
+                removed.push(chunk.chunk_type().clone());
+            }
+            !is_empty_ancillary
+        });
+        removed
+            .into_iter()
+            .for_each(|chunk_type| report.push(NormalizeAction::RemovedEmptyChunk { chunk_type }));
+
+        let is_iend = |chunk: &Chunk| &chunk.chunk_type().bytes() == b"IEND";
+        let iend_count = self.chunks.iter().filter(|chunk| is_iend(chunk)).count();
+        let iend_misplaced = iend_count == 1 && !self.chunks.last().is_some_and(is_iend);
+
+        let iend = self.chunks.iter().find(|chunk| is_iend(chunk)).cloned();
+        self.chunks.retain(|chunk| !is_iend(chunk));
+
+        let mut rank = 0;
+        let mut ranked: Vec<(u8, Chunk)> = self
+            .chunks
+            .drain(..)
+            .map(|chunk| {
+                rank = canonical_rank(chunk.chunk_type()).unwrap_or(rank);
+                (rank, chunk)
+            })
+            .collect();
+
+        let was_sorted = ranked.is_sorted_by_key(|(rank, _)| *rank);
+        ranked.sort_by_key(|(rank, _)| *rank);
+        self.chunks = ranked.into_iter().map(|(_, chunk)| chunk).collect();
+
+        if !was_sorted || iend_misplaced {
+            report.push(NormalizeAction::Reordered);
+        }
+
+        match iend {
+            Some(iend) => self.chunks.push(iend),
+            None => {
+                self.chunks.push(Chunk::new(ChunkType::IEND, vec![]));
+                report.push(NormalizeAction::AddedIend);
+            }
+        }
+
+        (1..iend_count).for_each(|_| report.push(NormalizeAction::RemovedDuplicateIend));
+
+        Ok(report)
+    }
+
+    /// Returns the width and height of the image in pixels, as recorded in the IHDR chunk.
+    ///
+    /// # Error
+    /// Returns an error if there is no IHDR chunk or its data is shorter than 13 bytes.
+    pub fn dimensions(&self) -> Result<(u32, u32)> {
+        let header = self.image_header()?;
+        Ok((header.width, header.height))
+    }
+
+    /// Returns the bit depth of the image, as recorded in the IHDR chunk.
+    ///
+    /// # Error
+    /// Returns an error if there is no IHDR chunk or its data is shorter than 13 bytes.
+    pub fn bit_depth(&self) -> Result<u8> {
+        Ok(self.image_header()?.bit_depth)
+    }
+
+    /// Returns the raw color type of the image, as recorded in the IHDR chunk.
+    /// Use [crate::ColorType] to interpret it.
+    ///
+    /// # Error
+    /// Returns an error if there is no IHDR chunk or its data is shorter than 13 bytes.
+    pub fn color_type(&self) -> Result<u8> {
+        Ok(self.image_header()?.color_type)
+    }
+
+    /// Returns the image header parsed from the IHDR chunk.
+    pub(crate) fn image_header(&self) -> Result<ImageHeader> {
+        let chunk = self
+            .chunks
+            .iter()
+            .find(|chunk| &chunk.chunk_type().bytes() == b"IHDR")
+            .ok_or_else(|| anyhow!("No IHDR chunk found!"))?;
+
+        ImageHeader::try_from(chunk)
+    }
+
+    /// Returns the data of each IDAT chunk in order, without copying.
+    ///
+    /// Useful for feeding a decompressor without concatenating the image data.
+    pub fn idat_slices(&self) -> impl Iterator<Item = &[u8]> {
+        self.iter_idat().map(|chunk| chunk.data())
+    }
+
+    /// Returns the number of IDAT chunks.
+    ///
+    /// Many small IDAT chunks usually indicate a streaming encoder,
+    /// while a single large one indicates a batch encoder.
+    pub fn idat_chunk_count(&self) -> usize {
+        self.iter_idat().count()
+    }
+
+    /// Returns the total number of compressed image data bytes across all IDAT chunks.
+    pub fn idat_total_bytes(&self) -> u64 {
+        self.idat_slices().map(|data| data.len() as u64).sum()
+    }
+
+    /// Returns an iterator over the IDAT chunks in file order.
+    pub fn iter_idat(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| &chunk.chunk_type().bytes() == b"IDAT")
+    }
+
+    /// Appends the data of all the IDAT chunks in order to the buffer,
+    /// so a single allocation can be reused across calls.
+    pub fn idat_stream_into(&self, buf: &mut Vec<u8>) {
+        buf.reserve(self.idat_slices().map(<[u8]>::len).sum());
+        self.idat_slices()
+            .for_each(|data| buf.extend_from_slice(data));
+    }
+
+    /// Replaces all the IDAT chunks with a single IDAT chunk
+    /// placed where the first IDAT chunk used to be.
+    ///
+    /// # Error
+    /// Returns an error if there are no IDAT chunks to replace.
+    pub(crate) fn replace_idat(&mut self, data: Vec<u8>) -> Result<()> {
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| &chunk.chunk_type().bytes() == b"IDAT")
+            .ok_or_else(|| anyhow!("No IDAT chunks found!"))?;
+
+        self.chunks
+            .retain(|chunk| &chunk.chunk_type().bytes() != b"IDAT");
+        self.chunks.insert(index, Chunk::new(ChunkType::IDAT, data));
+
+        Ok(())
+    }
+
+    /// Splits the image data into IDAT chunks of at most `max_size` bytes,
+    /// for decoders that cannot handle larger chunks.
+    ///
+    /// The IDAT chunks are first coalesced, then split again in place of the first one,
+    /// so the compressed stream and therefore the decoded image stay the same.
+    /// An empty stream is kept as a single empty IDAT chunk, which [Png::lint] warns about.
+    ///
+    /// # Error
+    /// Returns an error if `max_size` is zero or there are no IDAT chunks.
+    pub fn rechunk_idat(&mut self, max_size: usize) -> Result<()> {
+        if max_size == 0 {
+            bail!("IDAT chunk size must be greater than zero.");
+        }
+
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| &chunk.chunk_type().bytes() == b"IDAT")
+            .ok_or_else(|| anyhow!("No IDAT chunks found!"))?;
+
+        let mut stream = Vec::<u8>::new();
+        self.idat_stream_into(&mut stream);
+
+        let mut idat: Vec<Chunk> = stream
+            .chunks(max_size)
+            .map(|data| Chunk::new(ChunkType::IDAT, data.to_vec()))
+            .collect();
+        if idat.is_empty() {
+            idat.push(Chunk::new(ChunkType::IDAT, vec![]));
+        }
+
+        self.chunks
+            .retain(|chunk| &chunk.chunk_type().bytes() != b"IDAT");
+        self.chunks.splice(index..index, idat);
+
+        Ok(())
+    }
+
+    /// Shrinks the PNG to the smallest valid file with the same pixels by removing
+    /// ancillary and empty chunks, coalescing the IDAT chunks into one and clearing the trailer.
+    /// Returns the number of bytes saved.
+    ///
+    /// Ancillary chunks that change how the pixels are displayed, such as tRNS,
+    /// gAMA, cHRM, sRGB and iCCP, are kept. The image data itself is not recompressed.
+    pub fn shrink(&mut self) -> usize {
+        let before = self.size_breakdown().total();
+
+        self.chunks.retain(|chunk| {
+            let chunk_type = chunk.chunk_type();
+            (chunk_type.is_critical() || ChunkType::RENDERING.contains(chunk_type))
+                && (!chunk.data().is_empty() || matches!(&chunk_type.bytes(), b"IDAT" | b"IEND"))
+        });
+
+        if self.idat_slices().count() > 1 {
+            let mut data = Vec::<u8>::new();
+            self.idat_stream_into(&mut data);
+            // There are IDAT chunks to replace, so this cannot fail.
+            let _ = self.replace_idat(data);
+        }
+
+        self.trailer.clear();
+
+        before - self.size_breakdown().total()
+    }
+
+    /// Returns true if the PNG carries an embedded ICC profile (iCCP)
+    /// or declares the sRGB color space (sRGB).
+    pub fn has_color_profile(&self) -> bool {
+        self.chunks
+            .iter()
+            .any(|chunk| matches!(&chunk.chunk_type().bytes(), b"iCCP" | b"sRGB"))
+    }
+
+    /// Returns the data of the first iCCP chunk: the profile name, a null separator,
+    /// the compression method and the zlib compressed ICC profile.
+    pub fn iccp_bytes(&self) -> Option<&[u8]> {
+        self.chunks
+            .iter()
+            .find(|chunk| &chunk.chunk_type().bytes() == b"iCCP")
+            .map(|chunk| chunk.data())
+    }
+
+    /// Removes every iCCP chunk, returning how many were removed.
+    ///
+    /// Embedded profiles can take up kilobytes, but without one viewers fall back
+    /// to their default color space, so colors may shift for images that were not
+    /// authored in sRGB. Use [Png::strip_color_management] to also drop the
+    /// sRGB, cHRM and gAMA chunks.
+    pub fn strip_color_profile(&mut self) -> usize {
+        self.strip_chunk_types(&[b"iCCP"])
+    }
+
+    /// Removes every iCCP, sRGB, cHRM and gAMA chunk, returning how many were removed.
+    ///
+    /// This leaves viewers free to render the image however they like,
+    /// which may visibly change its colors and brightness.
+    pub fn strip_color_management(&mut self) -> usize {
+        self.strip_chunk_types(&[b"iCCP", b"sRGB", b"cHRM", b"gAMA"])
+    }
+
+    fn strip_chunk_types(&mut self, names: &[&[u8; 4]]) -> usize {
+        let original_len = self.chunks.len();
+        self.chunks
+            .retain(|chunk| !names.contains(&&chunk.chunk_type().bytes()));
+
+        original_len - self.chunks.len()
+    }
+
+    /// Rewrites the value of every tEXt chunk with the result of the closure,
+    /// which receives the keyword and the current value.
+    /// Returning `None` removes the chunk instead.
+    ///
+    /// Malformed tEXt chunks are left untouched.
+    ///
+    /// # Error
+    /// Returns an error if a new value cannot be encoded as Latin-1,
+    /// in which case the PNG is left unchanged.
+    pub fn map_text(&mut self, mut f: impl FnMut(&str, &str) -> Option<String>) -> Result<()> {
+        let mut chunks = Vec::with_capacity(self.chunks.len());
+
+        for chunk in &self.chunks {
+            match chunk.as_text() {
+                Ok((keyword, text)) => {
+                    if let Some(text) = f(&keyword, &text) {
+                        chunks.push(Chunk::new_text(&keyword, &text)?);
+                    }
+                }
+                Err(_) => chunks.push(chunk.clone()),
+            }
+        }
+
+        self.chunks = chunks;
+        Ok(())
+    }
+
+    /// Removes tEXt chunks repeating a keyword already used by another
+    /// tEXt chunk, keeping either the first or last one per the policy.
+    /// Returns the number of chunks removed.
+    ///
+    /// Malformed tEXt chunks are left untouched.
+    pub fn dedup_text_keywords(&mut self, keep: KeepPolicy) -> usize {
+        let keywords: Vec<Option<String>> = self
+            .chunks
+            .iter()
+            .map(|chunk| chunk.as_text().ok().map(|(keyword, _)| keyword))
+            .collect();
+
+        let mut indices: Vec<usize> = (0..keywords.len()).collect();
+        if keep == KeepPolicy::Last {
+            indices.reverse();
+        }
+
+        let mut seen = HashSet::<&str>::new();
+        let mut retain = vec![true; keywords.len()];
+        for i in indices {
+            if let Some(keyword) = &keywords[i] {
+                retain[i] = seen.insert(keyword);
+            }
+        }
+
+        let mut retain = retain.into_iter();
+        let original_len = self.chunks.len();
+        self.chunks.retain(|_| retain.next().unwrap_or(true));
+
+        original_len - self.chunks.len()
+    }
+
+    /// Returns the chunk holding the most data.
+    /// On ties, the earliest chunk is returned.
+    pub fn largest_chunk(&self) -> Option<&Chunk> {
+        self.chunks.iter().rev().max_by_key(|chunk| chunk.length())
+    }
+
+    /// Returns the chunk holding the least data.
+    /// On ties, the earliest chunk is returned.
+    pub fn smallest_chunk(&self) -> Option<&Chunk> {
+        self.chunks.iter().min_by_key(|chunk| chunk.length())
+    }
+
+    /// Returns every chunk sorted by data length, largest first,
+    /// leaving the order of the chunks in the PNG untouched.
+    /// Chunks of the same length keep their relative order.
+    pub fn chunks_sorted_by_size(&self) -> Vec<&Chunk> {
+        let mut chunks: Vec<&Chunk> = self.chunks.iter().collect();
+        chunks.sort_by_key(|chunk| std::cmp::Reverse(chunk.length()));
+        chunks
+    }
+
+    /// Returns every chunk whose data starts with the given prefix, whatever its type,
+    /// such as payloads carrying a known magic tag.
+    pub fn chunks_with_data_prefix(&self, prefix: &[u8]) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.data().starts_with(prefix))
+            .collect()
+    }
+
+    /// Returns the first chunk satisfying the given predicate.
+    pub fn find(&self, predicate: impl Fn(&Chunk) -> bool) -> Option<&Chunk> {
+        self.chunks.iter().find(|chunk| predicate(chunk))
+    }
+
+    /// Returns the indices of every chunk satisfying the given predicate.
+    pub fn find_all(&self, predicate: impl Fn(&Chunk) -> bool) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| predicate(chunk))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the bytes found after the IEND chunk
+    /// and any valid chunks following it.
+    pub fn trailer(&self) -> &[u8] {
+        &self.trailer
+    }
+
+    /// Returns the bytes left over after the IEND chunk, such as a payload
+    /// appended by another tool. Same as [Png::trailer].
+    ///
+    /// Parsing stops at the first bytes after IEND that are not a valid chunk,
+    /// so these bytes never fail it
+    /// and are written back unchanged by [Png::as_bytes].
+    pub fn trailing_data(&self) -> &[u8] {
+        self.trailer()
+    }
+
+    /// Sets the bytes written after the IEND chunk.
+    pub fn set_trailer(&mut self, trailer: Vec<u8>) {
+        self.trailer = trailer;
+    }
+
+    /// Sets the bytes found after the IEND chunk and returns the PNG,
+    /// for building files in a functional style.
+    ///
+    /// ```
+    /// use pngyou::{Chunk, ChunkType, Png};
+    /// use std::str::FromStr;
+    ///
+    /// let iend = Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]);
+    /// let png = Png::from_chunks(vec![iend]).with_trailer(b"hidden".to_vec());
+    ///
+    /// let bytes = png.as_bytes();
+    /// assert!(bytes.ends_with(b"hidden"));
+    ///
+    /// let png = Png::try_from(bytes.as_slice()).unwrap();
+    /// assert_eq!(png.trailer(), b"hidden");
+    /// assert!(png.without_trailer().trailer().is_empty());
+    /// ```
+    pub fn with_trailer(mut self, bytes: Vec<u8>) -> Self {
+        self.trailer = bytes;
+        self
+    }
+
+    /// Removes the bytes found after the IEND chunk and returns the PNG.
+    pub fn without_trailer(mut self) -> Self {
+        self.trailer.clear();
+        self
+    }
+
+    /// Removes any bytes following the IEND chunk,
+    /// so data hidden in the trailer is not carried along.
+    pub fn clear_trailer(&mut self) {
+        self.trailer.clear();
+    }
+
+    /// Splits the serialized size of the PNG into critical chunk data,
+    /// ancillary chunk data, framing overhead and trailing bytes.
+    pub fn size_breakdown(&self) -> SizeBreakdown {
+        let mut breakdown = SizeBreakdown {
+            overhead: Self::STANDARD_HEADER.len() + 12 * self.chunks.len(),
+            trailer: self.trailer.len(),
+            ..Default::default()
+        };
+
+        for chunk in &self.chunks {
+            match chunk.chunk_type().is_critical() {
+                true => breakdown.critical += chunk.data().len(),
+                false => breakdown.ancillary += chunk.data().len(),
+            }
+        }
+
+        breakdown
+    }
+
+    /// Renders the chunks as a bar such as `[IHDR][====IDAT====][tEXt][IEND]`,
+    /// where each chunk is padded in proportion to its serialized size.
+    ///
+    /// The map fits in [Png::ASCII_MAP_WIDTH] columns, see [Png::ascii_map_with_width].
+    pub fn ascii_map(&self) -> String {
+        self.ascii_map_with_width(Self::ASCII_MAP_WIDTH)
+    }
+
+    /// Renders the chunks like [Png::ascii_map], fitting the map in `width` columns
+    /// unless there are too many chunks, in which case every chunk is shown with its label only.
+    pub fn ascii_map_with_width(&self, width: usize) -> String {
+        let sizes: Vec<usize> = self
+            .chunks
+            .iter()
+            .map(|chunk| 12 + chunk.data().len())
+            .collect();
+        let total: usize = sizes.iter().sum();
+        let spare = width.saturating_sub(6 * self.chunks.len());
+
+        self.chunks
+            .iter()
+            .zip(sizes)
+            .map(|(chunk, size)| {
+                let padding = spare * size / total.max(1);
+                let left = "=".repeat(padding / 2);
+                let right = "=".repeat(padding - padding / 2);
+
+                format!("[{}{}{}]", left, chunk.chunk_type(), right)
+            })
+            .collect()
+    }
+
+    /// Returns how many payload bytes can be hidden using each embedding mode:
+    /// a custom chunk, the trailer, and LSB embedding at 1, 2 and 3 bits per channel.
+    ///
+    /// Each LSB capacity is [crate::stego::lsb_capacity_with] using the matching
+    /// [bits_per_channel](LsbOptions::bits_per_channel). At 1 bit this is
+    /// [crate::stego::lsb_capacity], the raw [Png::capacity_bytes] minus the length header.
+    ///
+    /// # Error
+    /// Returns an error if the IHDR chunk is missing or the image
+    /// format is not supported by the LSB embedder.
+    pub fn capacity_report(&self) -> Result<CapacityReport> {
+        let lsb = |bits_per_channel| {
+            let options = LsbOptions {
+                bits_per_channel,
+                ..Default::default()
+            };
+            lsb_capacity_with(self, &options)
+        };
+
+        Ok(CapacityReport {
+            chunk: CapacityReport::MAX_CHUNK_LENGTH,
+            trailer: None,
+            lsb: [lsb(1)?, lsb(2)?, lsb(3)?],
+        })
+    }
+
+    /// Returns the raw LSB capacity of the pixel data in bytes, hiding one bit
+    /// in every 8-bit sample: `width * height * samples_per_pixel / 8`.
+    ///
+    /// This is a quick estimate from the header alone. It does not account for the
+    /// length header written by [crate::stego::embed_lsb], nor for other bit depths
+    /// or [crate::stego::LsbOptions]; [crate::stego::lsb_capacity] does, and gives
+    /// the payload size [crate::stego::embed_lsb] actually accepts.
+    ///
+    /// # Error
+    /// Returns an error if the IHDR chunk is missing or invalid,
+    /// the image uses a palette, or its bit depth is not 8.
+    pub fn capacity_bytes(&self) -> Result<usize> {
+        let header = self.image_header()?;
+        if header.color_type == 3 {
+            bail!("Palette images are not supported for LSB capacity.");
+        }
+        if header.bit_depth != 8 {
+            bail!(
+                "Bit depth {} is not supported for LSB capacity. Expected 8.",
+                header.bit_depth
+            );
+        }
+
+        let samples = header.width as usize * header.height as usize * header.channels()?;
+        Ok(samples / 8)
+    }
+
+    /// Runs [Png::validate] and panics with every issue found, one per line.
+    ///
+    /// Meant for tests, so they can read as `png.assert_valid()`.
+    #[cfg(feature = "test-support")]
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        if let Err(issues) = self.validate() {
+            let issues: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+            panic!("PNG is invalid:\n{}", issues.join("\n"));
+        }
+    }
+
+    /// Checks the presence of a PLTE chunk against the color type declared in IHDR.
+    ///
+    /// A PLTE chunk is required for indexed-color images (color type 3),
+    /// optional for truecolor images (2 and 6) and forbidden for grayscale images (0 and 4).
+    ///
+    /// # Error
+    /// Returns every violation found, or an [Issue::InvalidHeader]
+    /// if the color type cannot be read.
+    pub fn validate_color_type_consistency(&self) -> Result<(), Vec<Issue>> {
+        let header = self.image_header().map_err(|err| {
+            vec![Issue::InvalidHeader {
+                reason: err.to_string(),
+            }]
+        })?;
+
+        let has_palette = self
+            .chunks
+            .iter()
+            .any(|chunk| &chunk.chunk_type().bytes() == b"PLTE");
+
+        let color_type = header.color_type;
+        match (color_type, has_palette) {
+            (3, false) => Err(vec![Issue::MissingPalette { color_type }]),
+            (0 | 4, true) => Err(vec![Issue::UnexpectedPalette { color_type }]),
+            _ => Ok(()),
+        }
+    }
+
+    /// Controls whether [Png::as_bytes] recomputes the CRC of every chunk
+    /// instead of writing the stored CRCs verbatim, which is off by default.
+    ///
+    /// Guards against stale CRCs in hand-assembled PNGs
+    /// without modifying the chunks themselves.
+    pub fn set_recompute_crc_on_write(&mut self, recompute: bool) {
+        self.recompute_crc_on_write = recompute;
+    }
+
+    /// Checks that all IDAT chunks are consecutive, as the PNG specification requires.
+    ///
+    /// # Error
+    /// Returns the indices of the chunks found in between the first and last IDAT chunks.
+    pub fn validate_idat_contiguity(&self) -> Result<(), Vec<usize>> {
+        let is_idat = |c: &Chunk| &c.chunk_type().bytes() == b"IDAT";
+
+        let (first, last) = match (
+            self.chunks.iter().position(is_idat),
+            self.chunks.iter().rposition(is_idat),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(()),
+        };
+
+        let breaks: Vec<usize> = (first..=last)
+            .filter(|&i| !is_idat(&self.chunks[i]))
+            .collect();
+
+        match breaks.is_empty() {
+            true => Ok(()),
+            false => Err(breaks),
+        }
+    }
+
+    /// Returns true if any IDAT chunk holds no data.
+    ///
+    /// Zero-length IDAT chunks are allowed by the specification,
+    /// but some decoders fail on them.
+    pub fn has_empty_idat(&self) -> bool {
+        self.iter_idat().any(|chunk| chunk.data().is_empty())
+    }
+
+    /// Checks that PLTE and tRNS chunks appear before the first IDAT chunk,
+    /// and that tRNS follows PLTE when both are present.
+    ///
+    /// # Error
+    /// Returns every ordering violation found, along with the index of the offending chunk.
+    pub fn validate_chunk_order(&self) -> Result<(), Vec<Issue>> {
+        let position = |name: &[u8; 4]| {
+            self.chunks
+                .iter()
+                .position(|c| &c.chunk_type().bytes() == name)
+        };
+
+        let first_idat = position(b"IDAT");
+        let palette = position(b"PLTE");
+        let mut issues = Vec::<Issue>::new();
+
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let chunk_type = chunk.chunk_type();
+            if !matches!(&chunk_type.bytes(), b"PLTE" | b"tRNS") {
+                continue;
+            }
+
+            if first_idat.is_some_and(|idat| index > idat) {
+                issues.push(Issue::AfterIdat {
+                    index,
+                    chunk_type: chunk_type.clone(),
+                });
+            }
+
+            if &chunk_type.bytes() == b"tRNS" && palette.is_some_and(|plte| index < plte) {
+                issues.push(Issue::TransparencyBeforePalette { index });
+            }
+        }
+
+        match issues.is_empty() {
+            true => Ok(()),
+            false => Err(issues),
+        }
+    }
+
+    /// Checks the overall layout: exactly one IHDR chunk placed first, right after
+    /// the signature, exactly one IEND chunk placed last, at least one IDAT chunk,
+    /// and chunk types made of four ASCII letters.
+    ///
+    /// # Error
+    /// Returns every violation found, in the order of the rules above.
+    pub fn validate_structure(&self) -> Result<(), Vec<Issue>> {
+        let is_type = |chunk: &Chunk, name: &[u8; 4]| &chunk.chunk_type().bytes() == name;
+        let mut issues = Vec::<Issue>::new();
+
+        match self.chunks.first() {
+            Some(chunk) if is_type(chunk, b"IHDR") => {}
+            first => issues.push(Issue::HeaderNotFirst {
+                chunk_type: first.map(|chunk| chunk.chunk_type().clone()),
+            }),
+        }
+
+        issues.extend(
+            self.chunks
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|(_, chunk)| is_type(chunk, b"IHDR"))
+                .map(|(index, _)| Issue::DuplicateHeader { index }),
+        );
+
+        match self.chunks.last() {
+            Some(chunk) if is_type(chunk, b"IEND") => {}
+            last => issues.push(Issue::EndNotLast {
+                chunk_type: last.map(|chunk| chunk.chunk_type().clone()),
+            }),
+        }
+
+        let last_index = self.chunks.len().saturating_sub(1);
+        issues.extend(
+            self.chunks
+                .iter()
+                .enumerate()
+                .filter(|&(index, chunk)| index < last_index && is_type(chunk, b"IEND"))
+                .map(|(index, _)| Issue::DuplicateEnd { index }),
+        );
+
+        if !self.chunks.iter().any(|chunk| is_type(chunk, b"IDAT")) {
+            issues.push(Issue::MissingImageData);
+        }
+
+        issues.extend(
+            self.chunks
+                .iter()
+                .enumerate()
+                .filter(|(_, chunk)| {
+                    !chunk
+                        .chunk_type()
+                        .bytes()
+                        .iter()
+                        .all(u8::is_ascii_alphabetic)
+                })
+                .map(|(index, chunk)| Issue::InvalidChunkType {
+                    index,
+                    chunk_type: chunk.chunk_type().clone(),
+                }),
+        );
+
+        match issues.is_empty() {
+            true => Ok(()),
+            false => Err(issues),
+        }
+    }
+
+    /// Runs every validation check, collecting all the issues found.
+    ///
+    /// Parsing stays lenient, so this can be used to lint files
+    /// that parse fine but do not follow the PNG specification,
+    /// or to check a PNG after inserting or removing chunks by hand.
+    ///
+    /// # Error
+    /// Returns every issue found. Structural issues from [Png::validate_structure]
+    /// come first, so the first issue names the first violated rule.
+    pub fn validate(&self) -> Result<(), Vec<Issue>> {
+        let mut issues = Vec::<Issue>::new();
+
+        if let Err(found) = self.validate_structure() {
+            issues.extend(found);
+        }
+
+        if let Err(found) = self.validate_color_type_consistency() {
+            issues.extend(found);
+        }
+
+        if let Err(found) = self.validate_chunk_order() {
+            issues.extend(found);
+        }
+
+        if let Err(indices) = self.validate_idat_contiguity() {
+            issues.extend(indices.into_iter().map(|index| Issue::InterleavedIdat {
+                index,
+                chunk_type: self.chunks[index].chunk_type().clone(),
+            }));
+        }
+
+        match issues.is_empty() {
+            true => Ok(()),
+            false => Err(issues),
+        }
+    }
+
+    /// Returns warnings about chunks that follow the PNG specification
+    /// but are known to trip up some decoders, such as zero-length IDAT chunks.
+    ///
+    /// Unlike [Png::validate], these never make a PNG invalid.
+    pub fn lint(&self) -> Vec<Issue> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| &chunk.chunk_type().bytes() == b"IDAT" && chunk.data().is_empty())
+            .map(|(index, _)| Issue::EmptyIdat { index })
+            .collect()
+    }
+
+    /// Splits the data between a new chunk of the given type and the trailer,
+    /// placing the leading `chunk_fraction` of the data in the chunk.
+    ///
+    /// The chunk data is prefixed with the length of the trailer part as a
+    /// 4-byte big-endian integer, so any existing trailer bytes are kept.
+    ///
+    /// # Error
+    /// Returns an error if the fraction is not between 0 and 1,
+    /// or the trailer part is too large to describe.
+    pub fn append_split_chunk_and_trailer(
+        &mut self,
+        chunk_type: ChunkType,
+        data: &[u8],
+        chunk_fraction: f32,
+    ) -> Result<()> {
+        if !(0.0..=1.0).contains(&chunk_fraction) {
+            bail!(
+                "Chunk fraction must be between 0 and 1, found {}.",
+                chunk_fraction
+            );
+        }
+
+        let split = ((data.len() as f32 * chunk_fraction).round() as usize).min(data.len());
+        let (chunk_part, trailer_part) = data.split_at(split);
+        let trailer_len = u32::try_from(trailer_part.len())?;
+
+        let mut chunk_data = Vec::<u8>::with_capacity(4 + chunk_part.len());
+        chunk_data.extend_from_slice(&trailer_len.to_be_bytes());
+        chunk_data.extend_from_slice(chunk_part);
+
+        self.append_chunk(Chunk::new(chunk_type, chunk_data));
+        self.trailer.extend_from_slice(trailer_part);
+        Ok(())
+    }
+
+    /// Reassembles data written by [Png::append_split_chunk_and_trailer],
+    /// joining the chunk part with the end of the trailer.
+    ///
+    /// # Error
+    /// Returns an error if the chunk of requested type is not found
+    /// or the parts do not line up.
+    pub fn read_split_chunk_and_trailer(&self, chunk_type: &ChunkType) -> Result<Vec<u8>> {
+        let (_, chunk) = self.find_last_chunk(chunk_type)?;
+
+        let data = chunk.data();
+        if data.len() < 4 {
+            bail!("Split chunk is too short to hold the trailer length.");
+        }
+
+        let trailer_len = u32::from_be_bytes(data[0..4].try_into()?) as usize;
+        if trailer_len > self.trailer.len() {
+            bail!(
+                "Split chunk expects {} trailer bytes, found {}.",
+                trailer_len,
+                self.trailer.len()
+            );
+        }
+
+        let mut joined = data[4..].to_vec();
+        joined.extend_from_slice(&self.trailer[self.trailer.len() - trailer_len..]);
+        Ok(joined)
+    }
+
+    /// Checks if both PNGs serialize to identical bytes,
+    /// including stored CRCs and trailers.
+    pub fn byte_exact_eq(&self, other: &Png) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+
+    /// Checks if both PNGs hold the same sequence of chunk types and data,
+    /// regardless of stored CRCs and trailers.
+    pub fn content_eq(&self, other: &Png) -> bool {
+        self.chunks.len() == other.chunks.len()
+            && self
+                .chunks
+                .iter()
+                .zip(&other.chunks)
+                .all(|(a, b)| a.chunk_type() == b.chunk_type() && a.data() == b.data())
+    }
+
+    /// Wraps the PNG in an SVG document embedding it as a base64 data URI.
+    ///
+    /// Missing dimensions are taken from the IHDR chunk.
+    ///
+    /// # Error
+    /// Returns an error if a dimension is not supplied and
+    /// the IHDR chunk is missing or invalid.
+    #[cfg(feature = "base64")]
+    pub fn to_svg_wrapper(&self, width: Option<u32>, height: Option<u32>) -> Result<String> {
+        use base64::Engine;
+        use base64::engine::general_purpose::STANDARD;
+
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                let header = self.image_header()?;
+                (
+                    width.unwrap_or(header.width),
+                    height.unwrap_or(header.height),
+                )
+            }
+        };
+
+        Ok(format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+                r#"<image width="{0}" height="{1}" href="data:image/png;base64,{2}"/>"#,
+                "</svg>"
+            ),
+            width,
+            height,
+            STANDARD.encode(self.as_bytes())
+        ))
+    }
+
+    /// Converts the entire PNG struct back to raw bytes.
+    ///
+    /// Stored CRCs are written verbatim unless [Png::set_recompute_crc_on_write]
+    /// is enabled, and the trailer is included.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(self.serialize_options())
+    }
+
+    /// Converts the PNG struct to raw bytes following the given options.
+    pub fn to_bytes_with(&self, options: SerializeOptions) -> Vec<u8> {
+        let mut bytes = Vec::<u8>::with_capacity(self.total_len());
+        self.write_parts(options, |part| bytes.extend_from_slice(part));
+
+        bytes
+    }
+
+    /// Returns the number of bytes [Png::as_bytes] produces, without serializing:
+    /// the signature, each chunk's data plus its 12 bytes of length, type
+    /// and CRC fields, and the trailer.
+    pub fn total_len(&self) -> usize {
+        let chunks: usize = self
+            .chunks
+            .iter()
+            .map(|chunk| 12 + chunk.data().len())
+            .sum();
+        self.signature.len() + chunks + self.trailer.len()
+    }
+
+    /// Writes the serialized PNG to the writer piece by piece,
+    /// without building the whole file in memory.
+    ///
+    /// # Error
+    /// Returns the first error encountered while writing.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut result = Ok(());
+        self.write_parts(self.serialize_options(), |part| {
+            if result.is_ok() {
+                result = writer.write_all(part);
+            }
+        });
+
+        result
+    }
+
+    /// Feeds the serialized bytes of the PNG to the hasher piece by piece,
+    /// without building the whole file in memory.
+    #[cfg(feature = "digest")]
+    pub fn hash_into(&self, hasher: &mut impl digest::Digest) {
+        self.write_parts(self.serialize_options(), |part| hasher.update(part));
+    }
+
+    /// Returns the options [Png::as_bytes] serializes with.
+    fn serialize_options(&self) -> SerializeOptions {
+        SerializeOptions {
+            recompute_crc: self.recompute_crc_on_write,
+            ..Default::default()
+        }
+    }
+
+    /// Passes each piece of the serialized PNG to the sink in order.
+    fn write_parts(&self, options: SerializeOptions, mut sink: impl FnMut(&[u8])) {
+        sink(&self.signature);
+
+        for chunk in &self.chunks {
+            let crc = match options.recompute_crc {
+                true => Chunk::compute_crc(chunk.chunk_type(), chunk.data()),
+                false => chunk.crc().to_be_bytes(),
+            };
+
+            sink(&chunk.length().to_be_bytes());
+            sink(&chunk.chunk_type().bytes());
+            sink(chunk.data());
+            sink(&crc);
+        }
+
+        if options.include_trailer {
+            sink(&self.trailer);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::image_header::ColorType;
+    use crate::limits::ParseBudget;
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    fn testing_chunks() -> Vec<Chunk> {
+        vec![
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+            chunk_from_strings("miDl", "I am another chunk").unwrap(),
+            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
+        ]
+    }
+
+    fn testing_png() -> Png {
+        let chunks = testing_chunks();
+        Png::from_chunks(chunks)
+    }
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk> {
+        use std::str::FromStr;
+
+        let chunk_type = ChunkType::from_str(chunk_type)?;
+        let data: Vec<u8> = data.bytes().collect();
+
+        Ok(Chunk::new(chunk_type, data))
+    }
+
+    fn png_with_color_type(
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: u8,
+        with_palette: bool,
+    ) -> Png {
+        let mut chunks = vec![Chunk::new_ihdr(width, height, bit_depth, color_type)];
+        if with_palette {
+            chunks.push(chunk_from_bytes("PLTE", vec![0, 0, 0]));
+        }
+        chunks.push(chunk_from_bytes("IDAT", vec![]));
+        chunks.push(chunk_from_bytes("IEND", vec![]));
+
+        Png::from_chunks(chunks)
+    }
+
+    fn chunk_from_bytes(chunk_type: &str, data: Vec<u8>) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data)
+    }
+
+    #[test]
+    fn test_from_chunks() {
+        let chunks = testing_chunks();
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_valid_from_bytes() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_ok());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = [13, 80, 78, 71, 13, 10, 26, 10]
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        #[rustfmt::skip]
+        let mut bad_chunk = vec![
+            0, 0, 0, 5,         // length
+            32, 117, 83, 116,   // Chunk Type (bad)
+            65, 64, 65, 66, 67, // Data
+            1, 2, 3, 4, 5       // CRC (bad)
+        ];
+
+        chunk_bytes.append(&mut bad_chunk);
+
+        let png = Png::try_from(chunk_bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_parse_error_variants() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[0] = 13;
+        assert!(matches!(
+            Png::try_from(bytes.as_slice()),
+            Err(PngError::MissingSignature { .. })
+        ));
+        assert!(matches!(
+            Png::try_from(&PNG_FILE[..4]),
+            Err(PngError::MissingSignature { .. })
+        ));
+
+        assert_eq!(
+            Png::try_from(&PNG_FILE[..30]).unwrap_err(),
+            PngError::TruncatedInput {
+                expected: 25,
+                found: 22
+            }
+        );
+        assert_eq!(
+            Png::try_from(&PNG_FILE[..10]).unwrap_err(),
+            PngError::TruncatedInput {
+                expected: 4,
+                found: 2
+            }
+        );
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[32] ^= 0xFF;
+        assert_eq!(
+            Png::try_from(bytes.as_slice()).unwrap_err(),
+            PngError::CrcMismatch {
+                expected: 0x1E3F88B1,
+                found: 0x1E3F884E
+            }
+        );
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[13] = b'4';
+        assert_eq!(
+            Png::try_from(bytes.as_slice()),
+            Err(PngError::InvalidChunkType {
+                chunk_type: b"I4DR".to_vec()
+            })
+        );
+
+        let limits = ParseLimits {
+            max_chunks: 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Png::try_from_with_limits(&PNG_FILE, &limits),
+            Err(PngError::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_error_downcast() {
+        let err = Png::from_reader(&PNG_FILE[..100]).unwrap_err();
+        let truncated = PngError::TruncatedInput {
+            expected: 4693,
+            found: 17,
+        };
+        assert_eq!(err.downcast_ref::<PngError>(), Some(&truncated));
+        assert_eq!(Png::try_from(&PNG_FILE[..100]).unwrap_err(), truncated);
+
+        let err = Png::from_reader(&PNG_FILE[1..]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PngError>(),
+            Some(PngError::MissingSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_chunks() {
+        let png = testing_png();
+        let chunks = png.chunks();
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_chunk_by_type() {
+        let png = testing_png();
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    }
+
+    #[test]
+    fn test_find() {
+        let png = testing_png();
+        let chunk = png
+            .find(|chunk| chunk.data().starts_with(b"I am another"))
+            .unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "miDl");
+
+        assert!(
+            png.find(|chunk| chunk.data().starts_with(b"PYOU"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_iter_idat() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.replace_idat(b"first".to_vec()).unwrap();
+        png.append_chunk(chunk_from_strings("tEXt", "between").unwrap());
+        png.append_chunk(chunk_from_strings("IDAT", "second").unwrap());
+        png.append_chunk(chunk_from_strings("IDAT", "third").unwrap());
+
+        let idats: Vec<&Chunk> = png.iter_idat().collect();
+        assert_eq!(idats.len(), 3);
+        assert!(
+            idats
+                .iter()
+                .all(|chunk| &chunk.chunk_type().bytes() == b"IDAT")
+        );
+
+        let data: Vec<&[u8]> = idats.iter().map(|chunk| chunk.data()).collect();
+        assert_eq!(data, [&b"first"[..], &b"second"[..], &b"third"[..]]);
+    }
+
+    #[test]
+    fn test_idat_chunk_count_and_total_bytes() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.idat_chunk_count(), 1);
+        assert_eq!(png.idat_total_bytes(), 4681);
+
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.replace_idat(b"first".to_vec()).unwrap();
+        png.append_chunk(chunk_from_strings("IDAT", "second").unwrap());
+        assert_eq!(png.idat_chunk_count(), 2);
+        assert_eq!(png.idat_total_bytes(), 11);
+
+        assert_eq!(testing_png().idat_chunk_count(), 0);
+        assert_eq!(testing_png().idat_total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_idat_slices_and_stream_into() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.replace_idat(b"first".to_vec()).unwrap();
+        png.append_chunk(chunk_from_strings("IDAT", "second").unwrap());
+
+        let slices: Vec<&[u8]> = png.idat_slices().collect();
+        assert_eq!(slices, [&b"first"[..], &b"second"[..]]);
+
+        let mut buf = b"kept".to_vec();
+        png.idat_stream_into(&mut buf);
+        assert_eq!(buf, b"keptfirstsecond");
+    }
+
+    #[test]
+    fn test_shrink() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (idat, rest) = png.chunks[4].data().split_at(1000);
+        let (idat, rest) = (idat.to_vec(), rest.to_vec());
+        png.chunks[4] = chunk_from_bytes("IDAT", idat);
+        png.chunks.insert(5, chunk_from_bytes("IDAT", rest));
+        png.append_chunk(chunk_from_bytes("TeST", vec![]));
+        png.set_trailer(b"trailer".to_vec());
+        let original = png.clone();
+
+        let saved = png.shrink();
+        assert_eq!(saved, original.as_bytes().len() - png.as_bytes().len());
+        assert!(saved > 0);
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            chunk_types,
+            ["IHDR", "sRGB", "gAMA", "IDAT", "RuSt", "IEND"]
+        );
+        assert!(png.trailer().is_empty());
+
+        let original_pixels = crate::raster::decode(&original).unwrap().pixels;
+        let shrunk_pixels = crate::raster::decode(&png).unwrap().pixels;
+        assert_eq!(shrunk_pixels, original_pixels);
+
+        assert_eq!(png.shrink(), 0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_shrink_keeps_transparency() {
+        let mut png = png_with_color_type(2, 1, 8, 2, false);
+        // A red pixel, marked transparent by tRNS, followed by a blue one.
+        crate::raster::compress(&mut png, &[0, 255, 0, 0, 0, 0, 255]).unwrap();
+        png.insert_chunk(1, chunk_from_bytes("tRNS", vec![0, 255, 0, 0, 0, 0]))
+            .unwrap();
+        png.insert_chunk(1, chunk_from_bytes("gAMA", 45455u32.to_be_bytes().to_vec()))
+            .unwrap();
+        png.append_chunk(chunk_from_bytes("tEXt", b"Comment\0hey".to_vec()));
+
+        let rgba = |png: &Png| {
+            image::load_from_memory_with_format(&png.as_bytes(), image::ImageFormat::Png)
+                .unwrap()
+                .to_rgba8()
+                .into_raw()
+        };
+        let original = rgba(&png);
+        assert_eq!(original, [255, 0, 0, 0, 0, 0, 255, 255]);
+
+        assert!(png.shrink() > 0);
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(chunk_types, ["IHDR", "gAMA", "tRNS", "IDAT", "IEND"]);
+        assert_eq!(rgba(&png), original);
+    }
+
+    #[test]
+    fn test_rechunk_idat() {
+        let mut png = png_with_color_type(100, 100, 8, 2, false);
+
+        // Noise barely compresses, so the stream spans several 8 KB chunks.
+        let mut state = 0x2545F491u32;
+        let filtered: Vec<u8> = (0..100 * (1 + 300))
+            .map(|i| match i % 301 {
+                0 => 0,
+                _ => {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                }
+            })
+            .collect();
+        crate::raster::compress(&mut png, &filtered).unwrap();
+        let original_pixels = crate::raster::decode(&png).unwrap().pixels;
+
+        png.rechunk_idat(8192).unwrap();
+        assert!(png.idat_chunk_count() > 1);
+        assert!(png.iter_idat().all(|chunk| chunk.length() <= 8192));
+        assert!(png.validate().is_ok());
+        assert_eq!(crate::raster::decode(&png).unwrap().pixels, original_pixels);
+
+        png.rechunk_idat(1 << 20).unwrap();
+        assert_eq!(png.idat_chunk_count(), 1);
+        assert_eq!(crate::raster::decode(&png).unwrap().pixels, original_pixels);
+
+        assert!(png.rechunk_idat(0).is_err());
+        assert!(testing_png().rechunk_idat(8192).is_err());
+    }
+
+    #[test]
+    fn test_chunks_with_data_prefix() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("RuSt", "PYOU first").unwrap());
+        png.append_chunk(chunk_from_strings("teSt", "not PYOU").unwrap());
+        png.append_chunk(chunk_from_strings("miDl", "PYOU second").unwrap());
+
+        let found: Vec<(String, &[u8])> = png
+            .chunks_with_data_prefix(b"PYOU")
+            .into_iter()
+            .map(|chunk| (chunk.chunk_type().to_string(), chunk.data()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("RuSt".to_string(), &b"PYOU first"[..]),
+                ("miDl".to_string(), &b"PYOU second"[..]),
+            ]
+        );
+
+        assert!(png.chunks_with_data_prefix(b"none").is_empty());
+        assert_eq!(png.chunks_with_data_prefix(b"").len(), png.chunk_count());
+    }
+
+    #[test]
+    fn test_replace_first_chunk() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        png.append_chunk(chunk_from_strings("RuSt", "second").unwrap());
+
+        let new_chunk = chunk_from_strings("RuSt", "updated message").unwrap();
+        png.replace_first_chunk(&chunk_type, new_chunk.clone())
+            .unwrap();
+
+        assert_eq!(png.get_chunk(5), Some(&new_chunk));
+        assert_eq!(png.chunks_by_type(&chunk_type)[1].data(), b"second");
+        assert_eq!(png.chunk_count(), 8);
+        assert!(png.validate().is_ok());
+
+        let missing = ChunkType::from_str("zzZz").unwrap();
+        let missing_chunk = chunk_from_strings("zzZz", "missing").unwrap();
+        assert!(png.replace_first_chunk(&missing, missing_chunk).is_err());
+
+        let before = png.clone();
+        assert!(
+            png.replace_first_chunk(&ChunkType::IEND, new_chunk)
+                .is_err()
+        );
+        assert_eq!(png, before);
+    }
+
+    #[test]
+    fn test_find_all() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert_eq!(png.find_all(|chunk| chunk.data().len() > 4), vec![0, 3, 4]);
+        assert_eq!(png.find_all(|chunk| chunk.data().is_empty()), vec![6]);
+        assert!(png.find_all(|chunk| chunk.data().len() > 5000).is_empty());
+    }
+
+    fn text_chunks_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new_text("Comment", "first").unwrap(),
+            Chunk::new_text("Author", "someone").unwrap(),
+            Chunk::new_text("Comment", "second").unwrap(),
+        ])
+    }
+
+    fn text_entries(png: &Png) -> Vec<(String, String)> {
+        png.chunks()
+            .iter()
+            .map(|chunk| chunk.as_text().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_map_text() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text("Author", "Jane Doe").unwrap());
+        png.append_chunk(Chunk::new_text("Comment", "Hello").unwrap());
+        png.append_chunk(Chunk::new_text("Title", "Dice").unwrap());
+        let original_len = png.chunk_count();
+
+        png.map_text(|keyword, value| match keyword {
+            "Author" => Some("REDACTED".to_string()),
+            "Comment" => None,
+            _ => Some(value.to_string()),
+        })
+        .unwrap();
+
+        let texts: Vec<(String, String)> = png
+            .chunks()
+            .iter()
+            .filter_map(|chunk| chunk.as_text().ok())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("Author".to_string(), "REDACTED".to_string()),
+                ("Title".to_string(), "Dice".to_string()),
+            ]
+        );
+        assert_eq!(png.chunk_count(), original_len - 1);
+        assert!(png.chunks().iter().all(|chunk| chunk.has_valid_crc()));
+    }
+
+    #[test]
+    fn test_map_text_invalid_value() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text("Author", "Jane Doe").unwrap());
+        let before = png.as_bytes();
+
+        assert!(png.map_text(|_, _| Some("\u{1F600}".to_string())).is_err());
+        assert_eq!(png.as_bytes(), before);
+    }
+
+    #[test]
+    fn test_dedup_text_keywords_keep_first() {
+        let mut png = text_chunks_png();
+
+        assert_eq!(png.dedup_text_keywords(KeepPolicy::First), 1);
+        assert_eq!(
+            text_entries(&png),
+            vec![
+                ("Comment".to_string(), "first".to_string()),
+                ("Author".to_string(), "someone".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_text_keywords_keep_last() {
+        let mut png = text_chunks_png();
+
+        assert_eq!(png.dedup_text_keywords(KeepPolicy::Last), 1);
+        assert_eq!(
+            text_entries(&png),
+            vec![
+                ("Author".to_string(), "someone".to_string()),
+                ("Comment".to_string(), "second".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_largest_and_smallest_chunk() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert_eq!(
+            png.largest_chunk().unwrap().chunk_type().to_string(),
+            "IDAT"
+        );
+        assert_eq!(
+            png.smallest_chunk().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+    }
+
+    #[test]
+    fn test_chunks_sorted_by_size() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let order: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        let sorted: Vec<String> = png
+            .chunks_sorted_by_size()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            sorted,
+            ["IDAT", "IHDR", "pHYs", "gAMA", "RuSt", "sRGB", "IEND"]
+        );
+
+        let unchanged: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(unchanged, order);
+    }
+
+    #[test]
+    fn test_largest_and_smallest_chunk_ties() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("FrSt", "tie").unwrap(),
+            chunk_from_strings("miDl", "the longest").unwrap(),
+            chunk_from_strings("LaSt", "the longest").unwrap(),
+            chunk_from_strings("EnDs", "tie").unwrap(),
+        ]);
+
+        assert_eq!(
+            png.largest_chunk().unwrap().chunk_type().to_string(),
+            "miDl"
+        );
+        assert_eq!(
+            png.smallest_chunk().unwrap().chunk_type().to_string(),
+            "FrSt"
+        );
+
+        let empty = Png::from_chunks(vec![]);
+        assert!(empty.largest_chunk().is_none());
+        assert!(empty.smallest_chunk().is_none());
+    }
+
+    #[test]
+    fn test_append_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let chunk = png.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+    }
+
+    #[test]
+    fn test_chunks_in_file_order() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(chunk_types.first().unwrap(), "IHDR");
+        assert_eq!(chunk_types.last().unwrap(), "IEND");
+        assert_eq!(
+            chunk_types,
+            ["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"]
+        );
+    }
+
+    #[test]
+    fn test_first_and_last_chunk() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(&png.first_chunk().unwrap().chunk_type().bytes(), b"IHDR");
+        assert_eq!(&png.last_chunk().unwrap().chunk_type().bytes(), b"IEND");
+
+        let empty = Png::from_chunks(vec![]);
+        assert!(empty.first_chunk().is_none());
+        assert!(empty.last_chunk().is_none());
+    }
+
+    #[test]
+    fn test_data_ranges() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let ranges = png.data_ranges();
+        assert_eq!(ranges.len(), png.chunks().len());
+
+        // IHDR data starts after the signature, length and type fields.
+        assert_eq!(ranges[0].1, 16..29);
+
+        // RuSt follows IHDR (25), sRGB (13), gAMA (16), pHYs (21) and IDAT (4693).
+        let (chunk_type, range) = &ranges[5];
+        assert_eq!(chunk_type.to_string(), "RuSt");
+        assert_eq!(range.clone(), 4784..4787);
+        assert_eq!(&PNG_FILE[range.clone()], b"hey");
+    }
+
+    #[test]
+    fn test_append_chunk_if_absent() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = chunk_from_strings("RuSt", "secret message").unwrap();
+
+        assert!(png.append_chunk_if_absent(chunk.clone()));
+        assert!(!png.append_chunk_if_absent(chunk));
+        assert_eq!(png.chunks_by_type(&chunk_type).len(), 2);
+        assert_eq!(png.last_chunk().unwrap().chunk_type().to_string(), "IEND");
+
+        assert!(png.append_chunk_if_absent(chunk_from_strings("RuSt", "another").unwrap()));
+        assert_eq!(png.chunks_by_type(&chunk_type).len(), 3);
+    }
+
+    #[test]
+    fn test_append_chunk_checked() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk_checked(chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+
+        let last_two: Vec<String> = png.chunks()[png.chunks().len() - 2..]
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(last_two, ["TeSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_append_chunk_checked_reserved_bit() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk = chunk_from_strings("Test", "Message").unwrap();
+
+        assert!(png.append_chunk_checked(chunk).is_err());
+        assert_eq!(png.chunks().len(), 7);
+    }
+
+    #[test]
+    fn test_append_chunk_checked_duplicate_singleton() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert!(png.append_chunk_checked(png.chunks()[0].clone()).is_err());
+        assert!(
+            png.append_chunk_checked(chunk_from_strings("IEND", "").unwrap())
+                .is_err()
+        );
+        assert!(
+            png.append_chunk_checked(chunk_from_strings("PLTE", "abc").unwrap())
+                .is_ok()
+        );
+        assert!(
+            png.append_chunk_checked(chunk_from_strings("PLTE", "abc").unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_append_chunk_checked_missing_iend() {
+        let mut png = testing_png();
+        let chunk = chunk_from_strings("TeSt", "Message").unwrap();
+
+        assert!(png.append_chunk_checked(chunk).is_err());
+    }
+
+    #[test]
+    fn test_swap_chunks() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.swap_chunks(1, 3).unwrap();
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            chunk_types,
+            ["IHDR", "pHYs", "gAMA", "sRGB", "IDAT", "RuSt", "IEND"]
+        );
+
+        assert!(png.swap_chunks(1, 7).is_err());
+    }
+
+    #[test]
+    fn test_swap_chunks_rejects_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let original = png.clone();
+
+        assert!(png.swap_chunks(5, 6).is_err());
+        assert!(png.swap_chunks(0, 1).is_err());
+        assert_eq!(png, original);
+
+        png.force_swap_chunks(5, 6).unwrap();
+        assert_eq!(&png.last_chunk().unwrap().chunk_type().bytes(), b"RuSt");
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let chunk_type = ChunkType::from_str("MeSg").unwrap();
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_message(chunk_type.clone(), b"versioned secret")
+            .unwrap();
+
+        let stored = png.chunks_by_type(&chunk_type)[0].data();
+        assert_eq!(stored[0], 1);
+        assert_eq!(&stored[1..5], &16u32.to_be_bytes());
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(png.read_message(&chunk_type).unwrap(), b"versioned secret");
+    }
+
+    #[test]
+    fn test_read_message_unsupported_version() {
+        let chunk_type = ChunkType::from_str("MeSg").unwrap();
+        let mut data = vec![99];
+        data.extend_from_slice(&6u32.to_be_bytes());
+        data.extend_from_slice(b"forged");
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(Chunk::new(chunk_type.clone(), data));
+
+        let err = png.read_message(&chunk_type).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported message version 99. Expected version 1."
+        );
+    }
+
+    #[test]
+    fn test_append_chunk_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let chunks = png.chunks();
+        assert_eq!(chunks[chunks.len() - 2].chunk_type().to_string(), "TeSt");
+        assert_eq!(chunks[chunks.len() - 1].chunk_type().to_string(), "IEND");
+
+        let mut without_iend = testing_png();
+        without_iend.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        assert_eq!(
+            without_iend
+                .chunks()
+                .last()
+                .unwrap()
+                .chunk_type()
+                .to_string(),
+            "TeSt"
+        );
+
+        let after_iend = chunk_from_strings("RuSt", "old message")
+            .unwrap()
+            .as_bytes();
+        let bytes = [&PNG_FILE[..], &after_iend].concat();
+        let mut old = Png::try_from(bytes.as_slice()).unwrap();
+        old.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let chunk_types: Vec<String> = old.chunks()[6..]
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(chunk_types, ["TeSt", "IEND", "RuSt"]);
+    }
+
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.insert_chunk(1, chunk_from_strings("ruSt", "after header").unwrap())
+            .unwrap();
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            chunk_types,
+            [
+                "IHDR", "ruSt", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert_chunk_keeps_iend_last() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk = chunk_from_strings("ruSt", "late").unwrap();
+
+        assert!(png.insert_chunk(7, chunk.clone()).is_err());
+        assert!(png.insert_chunk(8, chunk.clone()).is_err());
+        assert!(
+            png.insert_chunk(2, chunk_from_strings("IEND", "").unwrap())
+                .is_err()
+        );
+
+        png.insert_chunk(6, chunk).unwrap();
+        assert_eq!(&png.last_chunk().unwrap().chunk_type().bytes(), b"IEND");
+    }
+
+    #[test]
+    fn test_append_raw() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_raw("TeSt", b"Message".to_vec()).unwrap();
+
+        let chunk = png.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+        assert_eq!(&png.chunks()[png.chunks().len() - 2], chunk);
+
+        assert!(png.append_raw("T3St", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_insert_between_idat_and_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.insert_between_idat_and_iend(chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            chunk_types,
+            vec![
+                "IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "TeSt", "RuSt", "IEND"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert_between_idat_and_iend_missing() {
+        let mut png = testing_png();
+        let result = png.insert_between_idat_and_iend(chunk_from_strings("TeSt", "").unwrap());
+
+        assert!(result.is_err());
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[cfg(feature = "clock")]
+    #[test]
+    fn test_touch() {
+        use chrono::{Datelike, Utc};
+
+        let mut chunks = Png::try_from(&PNG_FILE[..]).unwrap().chunks().to_vec();
+        chunks.push(Chunk::new_time(2000, 1, 1, 0, 0, 0).unwrap());
+        chunks.push(Chunk::new_time(2001, 1, 1, 0, 0, 0).unwrap());
+        let mut png = Png::from_chunks(chunks);
+
+        png.touch().unwrap();
+
+        let time_chunks = png.chunks_by_type(&ChunkType::from_str("tIME").unwrap());
+        assert_eq!(time_chunks.len(), 1);
+        assert_eq!(png.chunks()[7].chunk_type().to_string(), "tIME");
+
+        let time = time_chunks[0].as_time().unwrap();
+        assert_eq!(i32::from(time.year), Utc::now().year());
+    }
+
+    #[cfg(feature = "clock")]
+    #[test]
+    fn test_touch_inserts_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.touch().unwrap();
+
+        let chunks = png.chunks();
+        assert_eq!(chunks.len(), 8);
+        assert_eq!(chunks[6].chunk_type().to_string(), "tIME");
+        assert_eq!(chunks[7].chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn test_clone_with_chunk() {
+        let png = testing_png();
+        let cloned = png.clone_with_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        assert_eq!(png.chunks().len(), 3);
+        assert!(png.chunk_by_type("TeSt").is_none());
+
+        assert_eq!(cloned.chunks().len(), 4);
+        let chunk = cloned.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+    }
+
+    #[test]
+    fn test_remove_first_chunk() {
+        let mut png = testing_png();
+        let chunk_type = ChunkType::from_str("TeSt").unwrap();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.remove_first_chunk(&chunk_type).unwrap();
+        let chunk = png.chunk_by_type("TeSt");
+        assert!(chunk.is_none());
+    }
+
+    #[test]
+    fn test_remove_last_chunk() {
+        let mut png = testing_png();
+        let chunk_type = ChunkType::from_str("TeSt").unwrap();
+        png.append_chunk(chunk_from_strings("TeSt", "First").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Second").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Third").unwrap());
+
+        let removed = png.remove_last_chunk(&chunk_type).unwrap();
+        assert_eq!(&removed.data_as_string().unwrap(), "Third");
+
+        let remaining: Vec<String> = png
+            .chunks_by_type(&chunk_type)
+            .iter()
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect();
+        assert_eq!(remaining, vec!["First", "Second"]);
+    }
+
+    #[test]
+    fn test_remove_last_chunk_missing() {
+        let mut png = testing_png();
+        let chunk_type = ChunkType::from_str("TeSt").unwrap();
+        let err = png.remove_last_chunk(&chunk_type).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PngError>(),
+            Some(&PngError::ChunkNotFound { chunk_type })
+        );
+    }
+
+    #[test]
+    fn test_read_message_missing_chunk() {
+        let png = testing_png();
+        let chunk_type = ChunkType::from_str("TeSt").unwrap();
+        let err = png.read_message(&chunk_type).unwrap_err();
+        assert_eq!(err.to_string(), "Chunk of type 'TeSt' not found.");
+        assert!(matches!(
+            err.downcast_ref::<PngError>(),
+            Some(PngError::ChunkNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_remove_all_chunks() {
+        let mut png = testing_png();
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        png.append_chunk(chunk_from_strings("ruSt", "First").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Other").unwrap());
+        png.append_chunk(chunk_from_strings("ruSt", "Second").unwrap());
+        png.append_chunk(chunk_from_strings("ruSt", "Third").unwrap());
+
+        assert_eq!(png.remove_all_chunks(&chunk_type), 3);
+        assert!(png.chunks_by_type(&chunk_type).is_empty());
+        assert_eq!(png.chunk_count(), 4);
+        assert_eq!(png.remove_all_chunks(&chunk_type), 0);
+    }
+
+    #[test]
+    fn test_remove_all_chunks_skips_critical() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk_type = ChunkType::from_str("IEND").unwrap();
+
+        assert_eq!(png.remove_all_chunks(&chunk_type), 0);
+        assert_eq!(png.last_chunk().unwrap().chunk_type(), &chunk_type);
+    }
+
+    #[test]
+    fn test_rename_chunks() {
+        let mut png = testing_png();
+        let from = ChunkType::from_str("miDl").unwrap();
+        let to = ChunkType::from_str("NeWt").unwrap();
+
+        assert_eq!(png.rename_chunks(&from, to.clone()).unwrap(), 1);
+        assert!(png.chunks_by_type(&from).is_empty());
+
+        let renamed = png.chunks_by_type(&to)[0];
+        assert_eq!(&renamed.data_as_string().unwrap(), "I am another chunk");
+        assert!(renamed.has_valid_crc());
+
+        let iend = ChunkType::from_str("IEND").unwrap();
+        assert!(png.rename_chunks(&to, iend).is_err());
+    }
+
+    #[test]
+    fn test_rename_chunks_preserving_semantics() {
+        let mut png = testing_png();
+        let from = ChunkType::from_str("miDl").unwrap();
+
+        assert_eq!(
+            png.rename_chunks_preserving_semantics(&from, "NEWT")
+                .unwrap(),
+            1
+        );
+
+        let renamed = png.chunks()[1].chunk_type();
+        assert_eq!(&renamed.to_string(), "neWt");
+        assert_eq!(renamed.is_safe_to_copy(), from.is_safe_to_copy());
+        assert_eq!(renamed.properties(), from.properties());
+    }
+
+    #[test]
+    fn test_verify_and_normalize() {
+        let mut bad_crc_chunk = chunk_from_strings("RuSt", "secret").unwrap().as_bytes();
+        *bad_crc_chunk.last_mut().unwrap() ^= 0xFF;
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .copied()
+            .chain(chunk_from_strings("IDAT", "pixels").unwrap().as_bytes())
+            .chain(Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]).as_bytes())
+            .chain(chunk_from_strings("teXt", "").unwrap().as_bytes())
+            .chain(bad_crc_chunk)
+            .collect();
+
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+
+        let mut png = Png::from_bytes_unverified(&bytes).unwrap();
+        let report = png.verify_and_normalize(true).unwrap();
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(chunk_types, vec!["IHDR", "RuSt", "IDAT", "IEND"]);
+
+        assert_eq!(
+            report.actions(),
+            &[
+                NormalizeAction::RepairedCrc {
+                    index: 3,
+                    chunk_type: ChunkType::from_str("RuSt").unwrap()
+                },
+                NormalizeAction::RemovedEmptyChunk {
+                    chunk_type: ChunkType::from_str("teXt").unwrap()
+                },
+                NormalizeAction::Reordered,
+                NormalizeAction::AddedIend,
+            ]
+        );
+
+        assert!(Png::try_from(png.as_bytes().as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_and_normalize_without_repair() {
+        let mut bad_crc_chunk = chunk_from_strings("RuSt", "secret").unwrap().as_bytes();
+        *bad_crc_chunk.last_mut().unwrap() ^= 0xFF;
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .copied()
+            .chain(bad_crc_chunk)
+            .collect();
+
+        let mut png = Png::from_bytes_unverified(&bytes).unwrap();
+        let original = png.clone();
+
+        assert!(png.verify_and_normalize(false).is_err());
+        assert_eq!(png, original);
+    }
+
+    #[test]
+    fn test_verify_and_normalize_valid_png() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let report = png.verify_and_normalize(false).unwrap();
+
+        assert!(report.is_unchanged());
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let mut first = testing_png();
+        first.append_chunk(chunk_from_strings("IEND", "").unwrap());
+
+        let second = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        let bytes: Vec<u8> = first
+            .as_bytes()
+            .into_iter()
+            .chain(second.as_bytes())
+            .collect();
+
+        let pngs = Png::parse_all(&bytes).unwrap();
+
+        assert_eq!(pngs.len(), 2);
+        assert_eq!(pngs[0].chunks().len(), 4);
+        assert_eq!(pngs[1].chunks().len(), 7);
+    }
+
+    #[test]
+    fn test_parse_all_without_png() {
+        assert!(Png::parse_all(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_try_from_with_limits() {
+        let unlimited = ParseLimits::default();
+        assert!(Png::try_from_with_limits(&PNG_FILE, &unlimited).is_ok());
+
+        let few_chunks = ParseLimits {
+            max_chunks: 6,
+            ..Default::default()
+        };
+        assert!(Png::try_from_with_limits(&PNG_FILE, &few_chunks).is_err());
+
+        let small_input = ParseLimits {
+            max_input_bytes: 4802,
+            ..Default::default()
+        };
+        assert!(Png::try_from_with_limits(&PNG_FILE, &small_input).is_err());
+    }
+
+    #[test]
+    fn test_try_from_with_parse_budget() {
+        let chunks: Vec<Chunk> = (0..10)
+            .map(|_| chunk_from_bytes("teXt", vec![0; 1000]))
+            .chain([chunk_from_bytes("IEND", vec![])])
+            .collect();
+        let bytes = Png::from_chunks(chunks).as_bytes();
+
+        let budget = |max_alloc_bytes| ParseLimits {
+            budget: ParseBudget { max_alloc_bytes },
+            ..Default::default()
+        };
+
+        assert!(Png::try_from_with_limits(&bytes, &budget(10_000)).is_ok());
+
+        let err = Png::try_from_with_limits(&bytes, &budget(5_500)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse budget of 5500 bytes exceeded at chunk index 5."
+        );
+
+        let with_trailer = [bytes.as_slice(), &[0; 10]].concat();
+        assert!(Png::try_from_with_limits(&with_trailer, &budget(10_000)).is_err());
+    }
+
+    #[test]
+    fn test_trailer() {
+        let bytes: Vec<u8> = PNG_FILE
+            .iter()
+            .chain(b"hidden after IEND")
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.chunks().len(), 7);
+        assert_eq!(png.trailer(), b"hidden after IEND");
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_parse_is_lossless() {
+        let after_iend = chunk_from_strings("RuSt", "after IEND").unwrap().as_bytes();
+        let mut duplicate_iend = PNG_FILE.to_vec();
+        duplicate_iend.extend_from_slice(&PNG_FILE[PNG_FILE.len() - 12..]);
+
+        let quirky_files = [
+            [&PNG_FILE[..], b"trailer"].concat(),
+            [&PNG_FILE[..], &after_iend, b"trailer"].concat(),
+            [&PNG_FILE[..], &PNG_FILE[..]].concat(),
+            duplicate_iend,
+        ];
+        for bytes in quirky_files {
+            let png = Png::try_from(bytes.as_slice()).unwrap();
+            assert_eq!(png.as_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_parse_chunks_after_iend() {
+        // Older versions appended messages after IEND.
+        let after_iend = chunk_from_strings("RuSt", "after IEND").unwrap().as_bytes();
+        let bytes = [&PNG_FILE[..], &after_iend, b"trailer"].concat();
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.chunks().len(), 8);
+        assert_eq!(png.chunks()[6].chunk_type(), &ChunkType::IEND);
+        assert_eq!(png.chunks()[7].data_as_string().unwrap(), "after IEND");
+        assert_eq!(png.trailer(), b"trailer");
+        assert_eq!(png.as_bytes(), bytes);
+
+        let mut corrupted = [&PNG_FILE[..], &after_iend].concat();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        let png = Png::try_from(corrupted.as_slice()).unwrap();
+        assert_eq!(png.chunks().len(), 7);
+        assert_eq!(png.trailer().len(), after_iend.len());
+    }
+
+    #[test]
+    fn test_ascii_map() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let map = png.ascii_map();
+
+        assert!(map.len() <= 80);
+        assert!(map.starts_with("[IHDR]"));
+        assert!(map.ends_with("[IEND]"));
+        assert!(map.contains("=IDAT="));
+
+        let labels: Vec<&str> = map
+            .split(['[', ']', '='])
+            .filter(|label| !label.is_empty())
+            .collect();
+        assert_eq!(
+            labels,
+            ["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"]
+        );
+    }
+
+    #[test]
+    fn test_ascii_map_narrow() {
+        let png = testing_png();
+        assert_eq!(png.ascii_map_with_width(0), "[FrSt][miDl][LASt]");
+        assert_eq!(Png::from_chunks(vec![]).ascii_map(), "");
+    }
+
+    #[test]
+    fn test_color_profile() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.has_color_profile());
+        assert!(png.iccp_bytes().is_none());
+
+        let iccp = b"ICC Profile\0\0profile".to_vec();
+        png.insert_chunk(1, chunk_from_bytes("iCCP", iccp.clone()))
+            .unwrap();
+        assert_eq!(png.iccp_bytes(), Some(iccp.as_slice()));
+
+        assert_eq!(png.strip_color_profile(), 1);
+        assert!(png.iccp_bytes().is_none());
+        assert!(png.has_color_profile());
+        assert_eq!(png.strip_color_profile(), 0);
+    }
+
+    #[test]
+    fn test_strip_color_management() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let idat = png.chunk_by_type("IDAT").unwrap().clone();
+
+        assert_eq!(png.strip_color_management(), 2);
+        assert!(!png.has_color_profile());
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(chunk_types, ["IHDR", "pHYs", "IDAT", "RuSt", "IEND"]);
+        assert_eq!(png.chunk_by_type("IDAT"), Some(&idat));
+        assert!(png.validate().is_ok());
+    }
+
+    #[test]
+    fn test_size_breakdown() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_trailer(b"trailer".to_vec());
+        let breakdown = png.size_breakdown();
+
+        // RuSt has an uppercase first letter, so it counts as critical.
+        assert_eq!(breakdown.critical, 13 + 4681 + 3);
+        assert_eq!(breakdown.ancillary, 1 + 4 + 9);
+        assert_eq!(breakdown.overhead, 8 + 7 * 12);
+        assert_eq!(breakdown.trailer, 7);
+        assert_eq!(breakdown.total(), png.as_bytes().len());
+    }
+
+    #[test]
+    fn test_capacity_report() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let report = png.capacity_report().unwrap();
+
+        // 50x50 RGBA at 8 bits has 10000 samples, minus the 4 byte length header.
+        assert_eq!(report.lsb, [1246, 2496, 3746]);
+        assert_eq!(report.lsb_bits(1), Some(1246));
+        assert_eq!(report.lsb_bits(4), None);
+        assert_eq!(report.chunk, CapacityReport::MAX_CHUNK_LENGTH);
+        assert_eq!(report.trailer, None);
+
+        assert!(testing_png().capacity_report().is_err());
+    }
+
+    #[test]
+    fn test_validate_color_type_consistency() {
+        assert!(
+            Png::try_from(&PNG_FILE[..])
+                .unwrap()
+                .validate_color_type_consistency()
+                .is_ok()
+        );
+        assert!(
+            png_with_color_type(1, 1, 8, 3, true)
+                .validate_color_type_consistency()
+                .is_ok()
+        );
+        assert!(
+            png_with_color_type(1, 1, 8, 2, true)
+                .validate_color_type_consistency()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_color_type_consistency_missing_palette() {
+        assert_eq!(
+            png_with_color_type(1, 1, 8, 3, false).validate_color_type_consistency(),
+            Err(vec![Issue::MissingPalette { color_type: 3 }])
+        );
+    }
+
+    #[test]
+    fn test_validate_color_type_consistency_unexpected_palette() {
+        assert_eq!(
+            png_with_color_type(1, 1, 8, 0, true).validate_color_type_consistency(),
+            Err(vec![Issue::UnexpectedPalette { color_type: 0 }])
+        );
+        assert!(matches!(
+            testing_png().validate_color_type_consistency().unwrap_err()[..],
+            [Issue::InvalidHeader { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_validate_idat_contiguity() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.validate_idat_contiguity().is_ok());
+        assert!(png.validate().is_ok());
+
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.append_chunk(chunk_from_strings("tEXt", "interleaved").unwrap());
+        png.append_chunk(chunk_from_bytes("IDAT", vec![]));
+
+        assert_eq!(png.validate_idat_contiguity(), Err(vec![2]));
+        assert_eq!(
+            png.validate(),
+            Err(vec![Issue::InterleavedIdat {
+                index: 2,
+                chunk_type: ChunkType::from_str("tEXt").unwrap()
+            }])
+        );
+
+        let bytes = png.as_bytes();
+        assert!(Png::try_from(bytes.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_empty_idat() {
+        let png = png_with_color_type(1, 1, 8, 2, false);
+        assert!(png.has_empty_idat());
+        assert!(png.validate().is_ok());
+        assert_eq!(png.lint(), vec![Issue::EmptyIdat { index: 1 }]);
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(!png.has_empty_idat());
+        assert!(png.lint().is_empty());
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let appended: Vec<u8> = (0..100).collect();
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(&appended).copied().collect();
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.trailing_data(), appended.as_slice());
+        assert_eq!(png.chunk_count(), 7);
+        assert_eq!(png.as_bytes(), bytes);
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.trailing_data().is_empty());
+    }
+
+    #[test]
+    fn test_clear_trailer() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_trailer(b"hidden after IEND".to_vec());
+        png.clear_trailer();
+
+        assert!(png.trailer().is_empty());
+        let bytes = png.as_bytes();
+        assert_eq!(&bytes[bytes.len() - 8..bytes.len() - 4], b"IEND");
+        assert_eq!(bytes, PNG_FILE);
+    }
+
+    #[test]
+    fn test_validate_chunk_order() {
+        let png = png_with_color_type(1, 1, 8, 3, true);
+        assert!(png.validate_chunk_order().is_ok());
+
+        let mut png = png_with_color_type(1, 1, 8, 3, false);
+        png.append_chunk(chunk_from_bytes("tRNS", vec![0]));
+        png.append_chunk(chunk_from_bytes("PLTE", vec![0, 0, 0]));
+
+        assert_eq!(
+            png.validate_chunk_order(),
+            Err(vec![
+                Issue::AfterIdat {
+                    index: 2,
+                    chunk_type: ChunkType::from_str("tRNS").unwrap()
+                },
+                Issue::TransparencyBeforePalette { index: 2 },
+                Issue::AfterIdat {
+                    index: 3,
+                    chunk_type: ChunkType::from_str("PLTE").unwrap()
+                },
+            ])
+        );
+        assert!(png.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_structure() {
+        assert!(
+            Png::try_from(&PNG_FILE[..])
+                .unwrap()
+                .validate_structure()
+                .is_ok()
+        );
+        assert!(
+            png_with_color_type(1, 1, 8, 3, true)
+                .validate_structure()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_structure_header_not_first() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.force_swap_chunks(0, 1).unwrap();
+
+        let issues = png.validate().unwrap_err();
+        assert_eq!(
+            issues[0],
+            Issue::HeaderNotFirst {
+                chunk_type: Some(ChunkType::from_str("IDAT").unwrap())
+            }
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "The first chunk must be IHDR, found 'IDAT'"
+        );
+    }
+
+    #[test]
+    fn test_validate_structure_duplicates() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        let ihdr = png.chunks()[0].clone();
+        png.append_chunk(ihdr);
+        png.chunks.insert(1, chunk_from_bytes("IEND", vec![]));
+
+        assert_eq!(
+            png.validate_structure(),
+            Err(vec![
+                Issue::DuplicateHeader { index: 3 },
+                Issue::DuplicateEnd { index: 1 },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_structure_end_not_last() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.chunks
+            .push(chunk_from_strings("tEXt", "after IEND").unwrap());
+
+        assert_eq!(
+            png.validate().unwrap_err()[0],
+            Issue::EndNotLast {
+                chunk_type: Some(ChunkType::from_str("tEXt").unwrap())
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_structure_missing_chunks() {
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.remove_first_chunk(&ChunkType::from_str("IDAT").unwrap())
+            .unwrap();
+        assert_eq!(png.validate_structure(), Err(vec![Issue::MissingImageData]));
+
+        assert_eq!(
+            Png::from_chunks(vec![]).validate_structure(),
+            Err(vec![
+                Issue::HeaderNotFirst { chunk_type: None },
+                Issue::EndNotLast { chunk_type: None },
+                Issue::MissingImageData,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_structure_invalid_chunk_type() {
+        let chunk_type = ChunkType::from_bytes_unchecked(*b"I4AT");
+        let mut png = png_with_color_type(1, 1, 8, 2, false);
+        png.append_chunk(Chunk::new(chunk_type.clone(), vec![]));
+
+        let png = Png::from_bytes_unverified(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(
+            png.validate_structure(),
+            Err(vec![Issue::InvalidChunkType {
+                index: png.chunk_count() - 2,
+                chunk_type,
+            }])
+        );
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn test_assert_valid() {
+        Png::try_from(&PNG_FILE[..]).unwrap().assert_valid();
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    #[should_panic(expected = "PNG is invalid:\nColor type 0 must not have a PLTE chunk")]
+    fn test_assert_valid_panics() {
+        png_with_color_type(1, 1, 8, 0, true).assert_valid();
+    }
+
+    #[test]
+    fn test_split_chunk_and_trailer_round_trip() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"layered secret message";
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_trailer(b"junk".to_vec());
+        png.append_split_chunk_and_trailer(chunk_type.clone(), data, 0.5)
+            .unwrap();
+
+        let reparsed = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let chunk = reparsed.chunks_by_type(&chunk_type).pop().unwrap();
+        assert_eq!(&chunk.data()[4..], b"layered sec");
+        assert_eq!(reparsed.trailer(), b"junkret message");
+        assert_eq!(
+            reparsed.read_split_chunk_and_trailer(&chunk_type).unwrap(),
+            data
+        );
+
+        assert!(
+            png.append_split_chunk_and_trailer(chunk_type, data, 1.5)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_byte_exact_eq_and_content_eq_with_trailer() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut with_trailer = png.clone();
+        with_trailer.set_trailer(b"trailer".to_vec());
+
+        assert!(png.content_eq(&with_trailer));
+        assert!(!png.byte_exact_eq(&with_trailer));
+        assert!(png.byte_exact_eq(&png.clone()));
+    }
+
+    #[test]
+    fn test_from_bytes_unverified_preserves_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        // Last byte of the RuSt chunk's CRC, right before the 12 byte IEND chunk.
+        let crc_index = bytes.len() - 13;
+        bytes[crc_index] ^= 0xFF;
+
+        let png = Png::from_bytes_unverified(&bytes).unwrap();
+        let rust_chunk = png.chunk_by_type("RuSt").unwrap();
+        assert!(!rust_chunk.has_valid_crc());
+        assert_eq!(rust_chunk.crc().to_be_bytes()[3], bytes[crc_index]);
+
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_recompute_crc_on_write() {
+        let mut bytes = PNG_FILE.to_vec();
+        let crc_index = bytes.len() - 13;
+        bytes[crc_index] ^= 0xFF;
+
+        let mut png = Png::from_bytes_unverified(&bytes).unwrap();
+        assert_eq!(png.as_bytes(), bytes);
+
+        png.set_recompute_crc_on_write(true);
+        assert_eq!(png.as_bytes(), PNG_FILE);
+        assert!(!png.chunk_by_type("RuSt").unwrap().has_valid_crc());
+
+        png.set_recompute_crc_on_write(false);
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_eq_ignores_recompute_crc_on_write() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut recomputing = png.clone();
+        recomputing.set_recompute_crc_on_write(true);
+        assert_eq!(png, recomputing);
+
+        let mut with_trailer = png.clone();
+        with_trailer.set_trailer(b"trailer".to_vec());
+        assert_ne!(png, with_trailer);
+    }
+
+    #[test]
+    fn test_to_bytes_with() {
+        let mut stale = PNG_FILE.to_vec();
+        let crc_index = stale.len() - 13;
+        stale[crc_index] ^= 0xFF;
 
-    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        if bytes[0..8] != Self::STANDARD_HEADER {
-            bail!(
-                "Invalid PNG signature.\nExpected: {:X?}\nFound: {:X?}",
-                Self::STANDARD_HEADER,
-                &bytes[0..8]
-            );
+        let mut png = Png::from_bytes_unverified(&stale).unwrap();
+        png.set_trailer(b"trailer".to_vec());
+
+        let with_trailer = |bytes: &[u8]| [bytes, b"trailer"].concat();
+        let cases = [
+            (false, false, stale.clone()),
+            (false, true, with_trailer(&stale)),
+            (true, false, PNG_FILE.to_vec()),
+            (true, true, with_trailer(&PNG_FILE)),
+        ];
+
+        for (recompute_crc, include_trailer, expected) in cases {
+            let options = SerializeOptions {
+                recompute_crc,
+                include_trailer,
+            };
+            assert_eq!(png.to_bytes_with(options), expected);
         }
 
-        let mut chunks = Vec::<Chunk>::new();
+        assert_eq!(
+            png.to_bytes_with(SerializeOptions::default()),
+            png.as_bytes()
+        );
+    }
 
-        let mut i = 8;
-        while i < bytes.len() {
-            if i + 4 > bytes.len() {
-                bail!("Incomplete chunk length!");
-            }
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_hash_into() {
+        use sha2::{Digest, Sha256};
 
-            let length = u32::from_be_bytes(bytes[i..i + 4].try_into()?) as usize;
-            let total_chunk_size = 12 + length;
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_trailer(b"trailer".to_vec());
 
-            if i + total_chunk_size > bytes.len() {
-                bail!("Chunk length exceeds remaining bytes!");
-            }
+        let mut hasher = Sha256::new();
+        png.hash_into(&mut hasher);
 
-            chunks.push(Chunk::try_from(&bytes[i..(i + total_chunk_size)])?);
-            i += 12 + length;
-        }
+        assert_eq!(hasher.finalize(), Sha256::digest(png.as_bytes()));
+    }
 
-        Ok(Self { chunks })
+    #[test]
+    fn test_dimensions() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.dimensions().unwrap(), (50, 50));
     }
-}
 
-impl Display for Png {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
-        for byte in Self::STANDARD_HEADER {
-            write!(f, "{} ", byte)?;
-        }
+    #[test]
+    fn test_dimensions_invalid_header() {
+        let png = Png::from_chunks(vec![chunk_from_strings("IEND", "").unwrap()]);
+        assert!(png.dimensions().is_err());
 
-        for chunk in &self.chunks {
-            for byte in chunk.as_bytes() {
-                write!(f, "{} ", byte)?;
-            }
+        let png = Png::from_chunks(vec![chunk_from_strings("IHDR", "short").unwrap()]);
+        assert!(png.dimensions().is_err());
+    }
+
+    #[test]
+    fn test_bit_depth_and_color_type() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.bit_depth().unwrap(), 8);
+        assert_eq!(png.color_type().unwrap(), 6);
+
+        let png = Png::from_chunks(vec![chunk_from_strings("IEND", "").unwrap()]);
+        assert!(png.bit_depth().is_err());
+        assert!(png.color_type().is_err());
+    }
+
+    #[test]
+    fn test_color_types() {
+        let expected = [
+            (0, ColorType::Grayscale),
+            (2, ColorType::Rgb),
+            (3, ColorType::Palette),
+            (4, ColorType::GrayscaleAlpha),
+            (6, ColorType::Rgba),
+        ];
+
+        for (value, color_type) in expected {
+            let png = png_with_color_type(1, 1, 8, value, value == 3);
+            let raw = png.color_type().unwrap();
+
+            assert_eq!(raw, value);
+            assert_eq!(ColorType::try_from(raw).unwrap(), color_type);
         }
 
-        Ok(())
+        assert!(ColorType::try_from(5).is_err());
     }
-}
 
-impl Png {
-    const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    #[cfg(feature = "qr")]
+    #[test]
+    fn test_qr_message_round_trip() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk_type = ChunkType::from_str("qrCd").unwrap();
+        png.embed_qr_message(chunk_type.clone(), "Meet at dawn")
+            .unwrap();
 
-    /// Creates a new [Png] instance from a vector of existing chunks.
-    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Self { chunks }
+        let mut png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(png.read_qr_message(&chunk_type).unwrap(), "Meet at dawn");
+
+        let mut data = png.remove_first_chunk(&chunk_type).unwrap().data().to_vec();
+        data[2] ^= 0xFF;
+        data[20] ^= 0xFF;
+        png.append_chunk(Chunk::new(chunk_type.clone(), data));
+
+        assert_eq!(png.read_qr_message(&chunk_type).unwrap(), "Meet at dawn");
+
+        let mut data = png.remove_first_chunk(&chunk_type).unwrap().data().to_vec();
+        data[0] ^= 0xFF;
+        png.append_chunk(Chunk::new(chunk_type.clone(), data));
+
+        assert_eq!(png.read_qr_message(&chunk_type).unwrap(), "Meet at dawn");
     }
 
-    /// Appends a new chunk to existing list of chunks.
-    pub fn append_chunk(&mut self, chunk: Chunk) {
-        self.chunks.push(chunk);
+    #[test]
+    fn test_from_reader() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend_from_slice(b"trailer");
+        let mut cursor = Cursor::new(bytes);
+
+        let png = Png::from_reader(&mut cursor).unwrap();
+        assert!(png.byte_exact_eq(&Png::try_from(&PNG_FILE[..]).unwrap()));
+
+        let mut remaining = Vec::new();
+        cursor.read_to_end(&mut remaining).unwrap();
+        assert_eq!(remaining, b"trailer");
     }
 
-    /// Returns a list of all the chunks of a specific chunk type.
-    pub fn chunks_by_type(&self, chunk_type: &ChunkType) -> Vec<&Chunk> {
-        self.chunks
-            .iter()
-            .filter(|c| c.chunk_type() == chunk_type)
-            .collect()
+    #[test]
+    fn test_from_reader_with_limits() {
+        // A chunk declaring a huge length, followed by an endless stream of data.
+        let huge_chunk = |length: u32| {
+            let header = [&Png::STANDARD_HEADER[..], &length.to_be_bytes(), b"RuSt"].concat();
+            Cursor::new(header).chain(std::io::repeat(0))
+        };
+
+        let err = Png::from_reader(huge_chunk(u32::MAX)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Chunk length of 4294967295 bytes exceeds the PNG limit of 2147483647 bytes."
+        );
+
+        let limits = ParseLimits {
+            max_input_bytes: 1 << 20,
+            ..Default::default()
+        };
+        let err = Png::from_reader_with_limits(huge_chunk(1 << 30), &limits).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PngError>(),
+            Some(PngError::LimitExceeded { .. })
+        ));
+
+        let budget = ParseLimits {
+            budget: ParseBudget {
+                max_alloc_bytes: 1 << 20,
+            },
+            ..Default::default()
+        };
+        assert!(Png::from_reader_with_limits(huge_chunk(1 << 30), &budget).is_err());
+
+        let few_chunks = ParseLimits {
+            max_chunks: 6,
+            ..Default::default()
+        };
+        assert!(Png::from_reader_with_limits(&PNG_FILE[..], &few_chunks).is_err());
+        assert!(Png::from_reader_with_limits(&PNG_FILE[..], &ParseLimits::default()).is_ok());
     }
 
-    /// Removes the first matching chunk of a particular type.
-    ///
-    /// # Error
-    /// Returns an error if there are no chunks or
-    /// the chunk of requested type is not found.
-    pub fn remove_first_chunk(&mut self, chunk_type: &ChunkType) -> Result<Chunk> {
-        if self.chunks.is_empty() {
-            bail!("No chunks to remove from!");
+    #[test]
+    fn test_from_reader_truncated() {
+        for len in [4, 10, 100, PNG_FILE.len() - 1] {
+            let cursor = Cursor::new(&PNG_FILE[..len]);
+            assert!(Png::from_reader(cursor).is_err());
         }
+    }
 
-        let index = match self
-            .chunks
-            .iter()
-            .position(|c| c.chunk_type() == chunk_type)
-        {
-            Some(i) => i,
-            None => bail!("Chunk of type '{}' not found.", chunk_type),
-        };
+    #[test]
+    fn test_write_to() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_trailer(b"trailer".to_vec());
 
-        Ok(self.chunks.remove(index))
+        let mut cursor = Cursor::new(Vec::new());
+        png.write_to(&mut cursor).unwrap();
+
+        assert_eq!(cursor.into_inner(), png.as_bytes());
     }
 
-    /// Returns the standard 8 bits header for a PNG file.
-    pub fn header(&self) -> &[u8; 8] {
-        &Self::STANDARD_HEADER
+    #[test]
+    fn test_set_signature() {
+        let signature = *b"\x89MYFMT\r\n";
+        let mut png = testing_png();
+        png.set_signature(signature);
+
+        let bytes = png.as_bytes();
+        assert!(bytes.starts_with(&signature));
+        assert_eq!(png.header(), &signature);
+
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+        let parsed = Png::try_from_with_signature(&bytes, signature).unwrap();
+        assert_eq!(parsed.as_bytes(), bytes);
     }
 
-    /// Returns a slice of existing chunks.
-    pub fn chunks(&self) -> &[Chunk] {
-        self.chunks.as_slice()
+    #[test]
+    fn test_total_len() {
+        let mut with_trailer = Png::try_from(&PNG_FILE[..]).unwrap();
+        with_trailer.set_trailer(b"trailer".to_vec());
+
+        let fixtures = [
+            Png::try_from(&PNG_FILE[..]).unwrap(),
+            with_trailer,
+            testing_png(),
+            Png::from_chunks(vec![]),
+        ];
+        for png in fixtures {
+            assert_eq!(png.total_len(), png.as_bytes().len());
+        }
     }
 
-    /// Get the first matching chunk of a particular type.
-    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        let chunk_type = match ChunkType::from_str(chunk_type) {
-            Ok(chunk_type) => chunk_type,
-            Err(_) => return None,
-        };
+    #[test]
+    fn test_append_to_chunk_data() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(Chunk::new_text("Comment", "Hello").unwrap());
+        let chunk_count = png.chunk_count();
 
-        self.chunks
-            .iter()
-            .find(|&chunk| chunk_type == *chunk.chunk_type())
-            .map(|v| v as _)
+        let chunk_type = ChunkType::from_str("tEXt").unwrap();
+        png.append_to_chunk_data(&chunk_type, b"\0hidden").unwrap();
+        assert_eq!(png.chunk_count(), chunk_count);
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let chunk = png.chunk_by_type("tEXt").unwrap();
+        assert_eq!(chunk.data(), b"Comment\0Hello\0hidden");
+        assert_eq!(chunk.length(), 20);
+        assert!(chunk.has_valid_crc());
     }
 
-    /// Converts the entire PNG struct back to raw bytes.
-    pub fn as_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::<u8>::new();
+    #[test]
+    fn test_append_to_chunk_data_invalid() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
 
-        bytes.extend_from_slice(&Self::STANDARD_HEADER);
-        self.chunks
-            .iter()
-            .for_each(|chunk| bytes.extend_from_slice(&chunk.as_bytes()));
+        let missing = ChunkType::from_str("tEXt").unwrap();
+        assert!(png.append_to_chunk_data(&missing, b"hidden").is_err());
 
-        bytes
+        let idat = ChunkType::from_str("IDAT").unwrap();
+        assert!(png.append_to_chunk_data(&idat, b"hidden").is_err());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::chunk::Chunk;
-    use crate::chunk_type::ChunkType;
-    use std::convert::TryFrom;
-    use std::str::FromStr;
+    #[test]
+    fn test_get_chunk() {
+        let png = testing_png();
 
-    fn testing_chunks() -> Vec<Chunk> {
-        vec![
-            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
-            chunk_from_strings("miDl", "I am another chunk").unwrap(),
-            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
-        ]
+        assert_eq!(&png.get_chunk(1).unwrap().chunk_type().to_string(), "miDl");
+        assert!(png.get_chunk(3).is_none());
     }
 
-    fn testing_png() -> Png {
-        let chunks = testing_chunks();
-        Png::from_chunks(chunks)
+    #[test]
+    fn test_chunk_at_mut() {
+        let mut bytes = chunk_from_strings("RuSt", "secret").unwrap().as_bytes();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+        let mut png = Png::from_chunks(vec![Chunk::from_bytes_unverified(&bytes).unwrap()]);
+
+        png.chunk_at_mut(0).unwrap().repair_crc();
+        assert!(png.get_chunk(0).unwrap().has_valid_crc());
+        assert!(png.chunk_at_mut(1).is_none());
     }
 
-    fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk> {
-        use std::str::FromStr;
+    #[test]
+    fn test_crc_of_first() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
 
-        let chunk_type = ChunkType::from_str(chunk_type)?;
-        let data: Vec<u8> = data.bytes().collect();
+        let ihdr = ChunkType::from_str("IHDR").unwrap();
+        assert_eq!(png.crc_of_first(&ihdr), Some(0x1E3F88B1));
 
-        Ok(Chunk::new(chunk_type, data))
+        let missing = ChunkType::from_str("tEXt").unwrap();
+        assert_eq!(png.crc_of_first(&missing), None);
     }
 
     #[test]
-    fn test_from_chunks() {
-        let chunks = testing_chunks();
-        let png = Png::from_chunks(chunks);
+    fn test_parse_until() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.insert_chunk(5, chunk_from_strings("IDAT", "second").unwrap())
+            .unwrap();
+        png.set_trailer(b"trailer".to_vec());
+        let bytes = png.as_bytes();
 
-        assert_eq!(png.chunks().len(), 3);
+        let idat = ChunkType::from_str("IDAT").unwrap();
+        let partial = Png::parse_until(&bytes, &idat).unwrap();
+
+        assert_eq!(partial.chunk_count(), 5);
+        assert_eq!(partial.last_chunk().unwrap().chunk_type(), &idat);
+        assert_eq!(partial.idat_chunk_count(), 1);
+        assert_eq!(partial.idat_total_bytes(), 4681);
+        assert!(partial.trailer().is_empty());
     }
 
     #[test]
-    fn test_valid_from_bytes() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
-
-        let bytes: Vec<u8> = Png::STANDARD_HEADER
-            .iter()
-            .chain(chunk_bytes.iter())
-            .copied()
-            .collect();
+    fn test_parse_until_missing_type() {
+        let bytes = Png::try_from(&PNG_FILE[..]).unwrap().as_bytes();
 
-        let png = Png::try_from(bytes.as_ref());
+        let missing = ChunkType::from_str("tEXt").unwrap();
+        let png = Png::parse_until(&bytes, &missing).unwrap();
+        assert_eq!(png.chunk_count(), 7);
 
-        assert!(png.is_ok());
+        assert!(Png::parse_until(&bytes[..100], &missing).is_err());
     }
 
     #[test]
-    fn test_invalid_header() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
+    fn test_capacity_bytes() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.capacity_bytes().unwrap(), 50 * 50 * 4 / 8);
 
-        let bytes: Vec<u8> = [13, 80, 78, 71, 13, 10, 26, 10]
-            .iter()
-            .chain(chunk_bytes.iter())
-            .copied()
-            .collect();
+        for (color_type, samples) in [(0, 1), (2, 3), (4, 2), (6, 4)] {
+            let png = png_with_color_type(20, 10, 8, color_type, false);
+            assert_eq!(png.capacity_bytes().unwrap(), 20 * 10 * samples / 8);
 
-        let png = Png::try_from(bytes.as_ref());
+            let usable = png.capacity_bytes().unwrap() - crate::stego::LENGTH_HEADER_SIZE;
+            assert_eq!(crate::stego::lsb_capacity(&png).unwrap(), usable);
+            assert_eq!(png.capacity_report().unwrap().lsb[0], usable);
+        }
+    }
 
-        assert!(png.is_err());
+    #[test]
+    fn test_capacity_bytes_unsupported() {
+        assert!(
+            png_with_color_type(20, 10, 16, 2, false)
+                .capacity_bytes()
+                .is_err()
+        );
+        assert!(
+            png_with_color_type(20, 10, 8, 3, false)
+                .capacity_bytes()
+                .is_err()
+        );
+        assert!(
+            png_with_color_type(20, 10, 4, 0, false)
+                .capacity_bytes()
+                .is_err()
+        );
+        assert!(testing_png().capacity_bytes().is_err());
     }
 
     #[test]
-    fn test_invalid_chunk() {
-        let mut chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
+    fn test_write_chunk_data() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let idat = ChunkType::from_str("IDAT").unwrap();
 
-        #[rustfmt::skip]
-        let mut bad_chunk = vec![
-            0, 0, 0, 5,         // length
-            32, 117, 83, 116,   // Chunk Type (bad)
-            65, 64, 65, 66, 67, // Data
-            1, 2, 3, 4, 5       // CRC (bad)
-        ];
+        let mut buffer = Vec::new();
+        png.write_chunk_data(&idat, &mut buffer).unwrap();
+        assert_eq!(buffer, png.chunk_by_type("IDAT").unwrap().data());
 
-        chunk_bytes.append(&mut bad_chunk);
+        let missing = ChunkType::from_str("tEXt").unwrap();
+        assert!(png.write_chunk_data(&missing, Vec::new()).is_err());
+    }
 
-        let png = Png::try_from(chunk_bytes.as_ref());
+    #[test]
+    fn test_write_chunk_data_concatenates() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "first,").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "second").unwrap());
 
-        assert!(png.is_err());
+        let mut buffer = Vec::new();
+        let chunk_type = ChunkType::from_str("TeSt").unwrap();
+        png.write_chunk_data(&chunk_type, &mut buffer).unwrap();
+        assert_eq!(buffer, b"first,second");
     }
 
     #[test]
-    fn test_list_chunks() {
-        let png = testing_png();
-        let chunks = png.chunks();
-        assert_eq!(chunks.len(), 3);
+    fn test_ordered_message_round_trip() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_ordered_message(chunk_type.clone(), b"split across parts", 7)
+            .unwrap();
+        assert_eq!(png.chunks_by_type(&chunk_type).len(), 3);
+
+        let mut png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        // Parts are reassembled by index, not by file order.
+        png.swap_chunks(6, 8).unwrap();
+
+        assert_eq!(
+            png.read_ordered_message(&chunk_type).unwrap(),
+            b"split across parts"
+        );
     }
 
     #[test]
-    fn test_chunk_by_type() {
-        let png = testing_png();
-        let chunk = png.chunk_by_type("FrSt").unwrap();
-        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    fn test_ordered_message_missing_part() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let mut png = testing_png();
+        png.append_ordered_message(chunk_type.clone(), b"split across parts", 7)
+            .unwrap();
+
+        let middle = png.find_all(|chunk| chunk.chunk_type() == &chunk_type)[1];
+        png.chunks.remove(middle);
+
+        let err = png.read_ordered_message(&chunk_type).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Ordered message is incomplete. Missing 1 of 3 parts: 1."
+        );
     }
 
     #[test]
-    fn test_append_chunk() {
+    fn test_ordered_message_huge_part_count() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let mut data = 0u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&u32::MAX.to_be_bytes());
+        data.extend_from_slice(b"part");
+
         let mut png = testing_png();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        let chunk = png.chunk_by_type("TeSt").unwrap();
-        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+        png.append_chunk(Chunk::new(chunk_type.clone(), data));
+
+        let err = png.read_ordered_message(&chunk_type).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Ordered message is incomplete. Missing {} of {} parts: 1, 2, 3, 4, 5, ....",
+                u32::MAX - 1,
+                u32::MAX
+            )
+        );
     }
 
     #[test]
-    fn test_remove_first_chunk() {
+    fn test_ordered_message_duplicate_parts() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
         let mut png = testing_png();
-        let chunk_type = ChunkType::from_str("TeSt").unwrap();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        png.remove_first_chunk(&chunk_type).unwrap();
-        let chunk = png.chunk_by_type("TeSt");
-        assert!(chunk.is_none());
+        png.append_ordered_message(chunk_type.clone(), b"split across parts", 7)
+            .unwrap();
+
+        // An identical copy of a part is harmless.
+        let first = png.chunks_by_type(&chunk_type)[0].clone();
+        png.append_chunk(first.clone());
+        assert_eq!(
+            png.read_ordered_message(&chunk_type).unwrap(),
+            b"split across parts"
+        );
+
+        let mut data = first.data()[..8].to_vec();
+        data.extend_from_slice(b"changed");
+        png.append_chunk(Chunk::new(chunk_type.clone(), data));
+
+        let err = png.read_ordered_message(&chunk_type).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Ordered message has conflicting data for part 0."
+        );
+    }
+
+    #[test]
+    fn test_chunk_count() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.chunk_count(), 7);
+
+        png.append_chunk(chunk_from_strings("TeSt", "data").unwrap());
+        assert_eq!(png.chunk_count(), 8);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "").unwrap(),
+            chunk_from_strings("IDAT", "").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert!(png.is_empty());
+
+        png.append_chunk(chunk_from_strings("teSt", "data").unwrap());
+        assert!(!png.is_empty());
+        assert!(!Png::try_from(&PNG_FILE[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_content_eq_with_stale_crc() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        let mut bytes = PNG_FILE.to_vec();
+        let crc_index = bytes.len() - 13;
+        bytes[crc_index] ^= 0xFF;
+        let stale = Png::from_bytes_unverified(&bytes).unwrap();
+
+        assert!(png.content_eq(&stale));
+        assert!(!png.byte_exact_eq(&stale));
+
+        let mut different = png.clone();
+        different.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        assert!(!png.content_eq(&different));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_to_svg_wrapper() {
+        use base64::Engine;
+        use base64::engine::general_purpose::STANDARD;
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let svg = png.to_svg_wrapper(None, None).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"width="50" height="50""#));
+        assert!(svg.contains(&format!(
+            "data:image/png;base64,{}",
+            STANDARD.encode(PNG_FILE)
+        )));
+
+        let svg = png.to_svg_wrapper(Some(100), None).unwrap();
+        assert!(svg.contains(r#"width="100" height="50""#));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_to_svg_wrapper_without_ihdr() {
+        let png = testing_png();
+
+        assert!(png.to_svg_wrapper(None, None).is_err());
+        assert!(png.to_svg_wrapper(Some(10), Some(20)).is_ok());
     }
 
     #[test]
@@ -304,7 +4338,7 @@ mod tests {
     }
 
     // This is the raw bytes for a shrunken version of the `dice.png` image on Wikipedia
-    const PNG_FILE: [u8; 4803] = [
+    pub(crate) const PNG_FILE: [u8; 4803] = [
         137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 50, 0, 0, 0, 50, 8,
         6, 0, 0, 0, 30, 63, 136, 177, 0, 0, 0, 1, 115, 82, 71, 66, 0, 174, 206, 28, 233, 0, 0, 0,
         4, 103, 65, 77, 65, 0, 0, 177, 143, 11, 252, 97, 5, 0, 0, 0, 9, 112, 72, 89, 115, 0, 0, 14,
@@ -0,0 +1,358 @@
+use anyhow::{Result, anyhow, bail};
+use qrcode::bits::Bits;
+use qrcode::ec::construct_codewords;
+use qrcode::types::{EcLevel, Version};
+
+/// The largest supported QR version. Up to version 9, byte mode
+/// uses an 8-bit character count, which keeps the decoder simple.
+const MAX_VERSION: usize = 9;
+
+/// The block layout of each supported QR version at error correction level H,
+/// as `(data bytes, block count, data bytes, block count, EC bytes per block)`.
+const BLOCKS: [(usize, usize, usize, usize, usize); MAX_VERSION] = [
+    (9, 1, 0, 0, 17),
+    (16, 1, 0, 0, 28),
+    (13, 2, 0, 0, 22),
+    (9, 4, 0, 0, 16),
+    (11, 2, 12, 2, 22),
+    (15, 4, 0, 0, 28),
+    (13, 4, 14, 1, 26),
+    (14, 4, 15, 2, 26),
+    (12, 4, 13, 4, 24),
+];
+
+/// The mode indicator for byte mode segments.
+const BYTE_MODE: u8 = 0b0100;
+
+/// Encodes the message as a QR byte stream at error correction level H,
+/// using the smallest version that fits.
+///
+/// The stream is a 1-byte version followed by the interleaved data
+/// and error correction codewords, as they would be placed in a QR symbol.
+/// The version byte has no error correction, so [decode] ignores it
+/// and picks the version from the number of codewords instead.
+///
+/// # Error
+/// Returns an error if the message does not fit in a version 9 QR code.
+pub(crate) fn encode(message: &[u8]) -> Result<Vec<u8>> {
+    for version in 1..=MAX_VERSION {
+        let qr_version = Version::Normal(version as i16);
+
+        let mut bits = Bits::new(qr_version);
+        if bits.push_byte_data(message).is_err() || bits.push_terminator(EcLevel::H).is_err() {
+            continue;
+        }
+
+        let (data, ec) = construct_codewords(&bits.into_bytes(), qr_version, EcLevel::H)
+            .map_err(|e| anyhow!("Failed to encode QR codewords: {}", e))?;
+
+        let mut stream = vec![version as u8];
+        stream.extend(data);
+        stream.extend(ec);
+        return Ok(stream);
+    }
+
+    bail!(
+        "Message of {} bytes is too long for a version {} QR code.",
+        message.len(),
+        MAX_VERSION
+    )
+}
+
+/// Decodes a QR byte stream written by [encode], correcting errors in each block.
+///
+/// Every supported version has a different number of codewords, so the version
+/// is found from the stream length, and the unprotected version byte is skipped.
+///
+/// # Error
+/// Returns an error if the stream length matches no supported version, a block
+/// has more errors than can be corrected or the data is not a byte mode segment.
+pub(crate) fn decode(stream: &[u8]) -> Result<Vec<u8>> {
+    let (_, codewords) = stream
+        .split_first()
+        .ok_or_else(|| anyhow!("QR stream is empty."))?;
+
+    let Some((sizes, ec_size)) =
+        BLOCKS
+            .iter()
+            .map(|&layout| block_sizes(layout))
+            .find(|(sizes, ec_size)| {
+                sizes.iter().sum::<usize>() + ec_size * sizes.len() == codewords.len()
+            })
+    else {
+        bail!(
+            "Invalid QR stream length. {} codewords match no supported version.",
+            codewords.len()
+        );
+    };
+
+    let data_len: usize = sizes.iter().sum();
+
+    let ec_sizes = vec![ec_size; sizes.len()];
+    let data_blocks = deinterleave(&codewords[..data_len], &sizes);
+    let ec_blocks = deinterleave(&codewords[data_len..], &ec_sizes);
+
+    let mut data = Vec::with_capacity(data_len);
+    for (mut block, ec) in data_blocks.into_iter().zip(ec_blocks) {
+        let size = block.len();
+        block.extend(ec);
+
+        correct_errors(&mut block, ec_size)?;
+        data.extend_from_slice(&block[..size]);
+    }
+
+    if data.len() < 2 || data[0] >> 4 != BYTE_MODE {
+        bail!("QR data is not a byte mode segment.");
+    }
+
+    let len = (((data[0] & 0x0F) << 4) | (data[1] >> 4)) as usize;
+    if data.len() < len + 2 {
+        bail!("QR segment length {} exceeds the available data.", len);
+    }
+
+    Ok((0..len)
+        .map(|i| (data[i + 1] << 4) | (data[i + 2] >> 4))
+        .collect())
+}
+
+/// Expands a version's block layout into the data size of every block,
+/// along with the number of error correction codewords per block.
+fn block_sizes(
+    (size_1, count_1, size_2, count_2, ec_size): (usize, usize, usize, usize, usize),
+) -> (Vec<usize>, usize) {
+    let sizes = [(size_1, count_1), (size_2, count_2)]
+        .into_iter()
+        .flat_map(|(size, count)| std::iter::repeat_n(size, count))
+        .collect();
+
+    (sizes, ec_size)
+}
+
+/// Splits interleaved codewords back into blocks of the given sizes.
+fn deinterleave(codewords: &[u8], sizes: &[usize]) -> Vec<Vec<u8>> {
+    let mut blocks: Vec<Vec<u8>> = sizes.iter().map(|&size| Vec::with_capacity(size)).collect();
+    let mut codewords = codewords.iter();
+
+    for i in 0..sizes.iter().copied().max().unwrap_or(0) {
+        for (block, &size) in blocks.iter_mut().zip(sizes) {
+            if i < size
+                && let Some(&codeword) = codewords.next()
+            {
+                block.push(codeword);
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Arithmetic in GF(2^8) with the QR primitive polynomial x^8 + x^4 + x^3 + x^2 + 1.
+struct Gf {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+const GF: Gf = {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11D;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+
+    Gf { exp, log }
+};
+
+impl Gf {
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        match (a, b) {
+            (0, _) | (_, 0) => 0,
+            _ => self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize],
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        match a {
+            0 => 0,
+            _ => self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize],
+        }
+    }
+
+    fn pow(&self, exponent: usize) -> u8 {
+        self.exp[exponent % 255]
+    }
+
+    /// Evaluates a polynomial whose coefficients are ordered from the lowest degree.
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+}
+
+/// Corrects errors in a Reed-Solomon block in place, the last `ec_size`
+/// bytes being the error correction codewords.
+///
+/// # Error
+/// Returns an error if the block holds more errors than can be corrected.
+fn correct_errors(block: &mut [u8], ec_size: usize) -> Result<()> {
+    let n = block.len();
+
+    // The block's first byte is the coefficient of its highest degree term.
+    let syndromes: Vec<u8> = (0..ec_size)
+        .map(|j| {
+            let x = GF.pow(j);
+            block.iter().fold(0, |acc, &c| GF.mul(acc, x) ^ c)
+        })
+        .collect();
+
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(());
+    }
+
+    // Berlekamp-Massey, finding the error locator polynomial.
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1u8;
+
+    for i in 0..ec_size {
+        let discrepancy = (1..=errors)
+            .filter(|&k| k < locator.len())
+            .fold(syndromes[i], |acc, k| {
+                acc ^ GF.mul(locator[k], syndromes[i - k])
+            });
+
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let scale = GF.div(discrepancy, last_discrepancy);
+        let mut updated = locator.clone();
+        updated.resize(updated.len().max(previous.len() + shift), 0);
+        for (k, &p) in previous.iter().enumerate() {
+            updated[k + shift] ^= GF.mul(scale, p);
+        }
+
+        if 2 * errors <= i {
+            previous = std::mem::replace(&mut locator, updated);
+            errors = i + 1 - errors;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+
+    // Chien search, finding the positions whose inverse is a root of the locator.
+    let positions: Vec<usize> = (0..n)
+        .filter(|&power| GF.eval(&locator, GF.pow(255 - power % 255)) == 0)
+        .collect();
+
+    if positions.len() != errors || 2 * errors > ec_size {
+        bail!("QR block has too many errors to correct.");
+    }
+
+    // Forney, computing the magnitude of each error.
+    let mut evaluator = vec![0u8; ec_size];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (k, &l) in locator.iter().enumerate() {
+            if i + k < ec_size {
+                evaluator[i + k] ^= GF.mul(s, l);
+            }
+        }
+    }
+
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(k, &l)| if k % 2 == 1 { l } else { 0 })
+        .collect();
+
+    for power in positions {
+        let x = GF.pow(power);
+        let x_inverse = GF.pow(255 - power % 255);
+
+        let denominator = GF.eval(&derivative, x_inverse);
+        if denominator == 0 {
+            bail!("QR block has too many errors to correct.");
+        }
+
+        let magnitude = GF.mul(x, GF.div(GF.eval(&evaluator, x_inverse), denominator));
+        block[n - 1 - power] ^= magnitude;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_each_version() {
+        for len in [0, 7, 14, 20, 34, 44, 58, 64, 84, 98] {
+            let message: Vec<u8> = (0..len as u8).collect();
+            let stream = encode(&message).unwrap();
+
+            assert_eq!(decode(&stream).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_message_too_long() {
+        assert!(encode(&[0; 99]).is_err());
+    }
+
+    #[test]
+    fn test_corrects_errors() {
+        let message = b"Meet at the usual place";
+        let mut stream = encode(message).unwrap();
+
+        for i in [1, 5, 17, 30, 41] {
+            stream[i] ^= 0xA5;
+        }
+
+        assert_eq!(decode(&stream).unwrap(), message);
+    }
+
+    #[test]
+    fn test_corrupted_version_byte() {
+        let message = b"Meet at the usual place";
+        let mut stream = encode(message).unwrap();
+        stream[0] ^= 0xFF;
+
+        assert_eq!(decode(&stream).unwrap(), message);
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let stream = encode(b"hi").unwrap();
+        assert!(decode(&stream[..stream.len() - 1]).is_err());
+        assert!(decode(&[]).is_err());
+    }
+
+    #[test]
+    fn test_too_many_errors() {
+        let mut stream = encode(b"hi").unwrap();
+        for byte in stream.iter_mut().skip(1).take(20) {
+            *byte ^= 0xFF;
+        }
+
+        assert!(decode(&stream).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use super::image_header::ImageHeader;
+use super::png::Png;
+use anyhow::{Result, bail};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+
+/// The unfiltered pixel data of a PNG.
+pub(crate) struct Raster {
+    pub(crate) header: ImageHeader,
+    /// Raw scanlines, without their leading filter-type bytes.
+    pub(crate) pixels: Vec<u8>,
+    /// The filter type originally used by each scanline.
+    pub(crate) filter_types: Vec<u8>,
+}
+
+/// Decompresses the IDAT stream of a PNG, returning its header along
+/// with the still filtered scanlines (each led by its filter-type byte).
+pub(crate) fn decompress(png: &Png) -> Result<(ImageHeader, Vec<u8>)> {
+    let header = png.image_header()?;
+    if header.interlace_method != 0 {
+        bail!("Interlaced images are not supported.");
+    }
+
+    if png.idat_slices().all(|data| data.is_empty()) {
+        bail!("No IDAT chunks found!");
+    }
+
+    let expected_len = header.height as usize * (header.scanline_len()? + 1);
+
+    // Bytes past the image are dropped anyway, so a zlib bomb
+    // cannot inflate more than the image needs.
+    let mut filtered = Vec::<u8>::new();
+    ZlibDecoder::new(SliceReader::new(png.idat_slices()))
+        .take(expected_len as u64 + 1)
+        .read_to_end(&mut filtered)?;
+
+    if filtered.len() < expected_len {
+        bail!("Pixel data is shorter than the image dimensions.");
+    }
+    filtered.truncate(expected_len);
+
+    Ok((header, filtered))
+}
+
+/// Reads a sequence of byte slices as one continuous stream.
+struct SliceReader<'a, I: Iterator<Item = &'a [u8]>> {
+    slices: I,
+    current: &'a [u8],
+}
+
+impl<'a, I: Iterator<Item = &'a [u8]>> SliceReader<'a, I> {
+    fn new(slices: I) -> Self {
+        Self {
+            slices,
+            current: &[],
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a [u8]>> Read for SliceReader<'a, I> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current.is_empty() {
+            match self.slices.next() {
+                Some(slice) => self.current = slice,
+                None => return Ok(0),
+            }
+        }
+
+        self.current.read(buf)
+    }
+}
+
+/// Compresses the filtered scanlines, replacing the IDAT chunks of the PNG with the result.
+pub(crate) fn compress(png: &mut Png, filtered: &[u8]) -> Result<()> {
+    let mut encoder = ZlibEncoder::new(Vec::<u8>::new(), Compression::default());
+    encoder.write_all(filtered)?;
+
+    png.replace_idat(encoder.finish()?)
+}
+
+/// Decompresses and unfilters the IDAT stream of a PNG.
+pub(crate) fn decode(png: &Png) -> Result<Raster> {
+    let (header, filtered) = decompress(png)?;
+    let stride = header.scanline_len()?;
+    let bpp = bytes_per_pixel(&header)?;
+
+    let mut pixels = vec![0u8; header.height as usize * stride];
+    let mut filter_types = Vec::<u8>::with_capacity(header.height as usize);
+
+    for (row, line) in filtered.chunks(stride + 1).enumerate() {
+        let filter_type = line[0];
+        filter_types.push(filter_type);
+
+        let (previous_rows, current_rows) = pixels.split_at_mut(row * stride);
+        let previous = match row {
+            0 => None,
+            _ => Some(&previous_rows[(row - 1) * stride..]),
+        };
+        let current = &mut current_rows[..stride];
+
+        for i in 0..stride {
+            let (a, b, c) = neighbours(current, previous, i, bpp);
+            current[i] = line[i + 1].wrapping_add(predict(filter_type, a, b, c)?);
+        }
+    }
+
+    Ok(Raster {
+        header,
+        pixels,
+        filter_types,
+    })
+}
+
+/// Filters each scanline with its recorded filter type and compresses the result,
+/// replacing the IDAT chunks of the PNG.
+pub(crate) fn encode(png: &mut Png, raster: &Raster) -> Result<()> {
+    let stride = raster.header.scanline_len()?;
+    let bpp = bytes_per_pixel(&raster.header)?;
+
+    let mut filtered = Vec::<u8>::with_capacity(raster.pixels.len() + raster.filter_types.len());
+
+    for (row, current) in raster.pixels.chunks(stride).enumerate() {
+        let filter_type = raster.filter_types[row];
+        let previous = match row {
+            0 => None,
+            _ => Some(&raster.pixels[(row - 1) * stride..row * stride]),
+        };
+
+        filtered.push(filter_type);
+        for i in 0..stride {
+            let (a, b, c) = neighbours(current, previous, i, bpp);
+            filtered.push(current[i].wrapping_sub(predict(filter_type, a, b, c)?));
+        }
+    }
+
+    compress(png, &filtered)
+}
+
+/// Returns the number of bytes in a complete pixel, rounded up to one byte.
+fn bytes_per_pixel(header: &ImageHeader) -> Result<usize> {
+    Ok((header.channels()? * header.bit_depth as usize)
+        .div_ceil(8)
+        .max(1))
+}
+
+/// Returns the bytes to the left, above and above-left of the given byte.
+fn neighbours(current: &[u8], previous: Option<&[u8]>, i: usize, bpp: usize) -> (u8, u8, u8) {
+    let a = if i >= bpp { current[i - bpp] } else { 0 };
+    let b = previous.map_or(0, |p| p[i]);
+    let c = if i >= bpp {
+        previous.map_or(0, |p| p[i - bpp])
+    } else {
+        0
+    };
+
+    (a, b, c)
+}
+
+fn predict(filter_type: u8, a: u8, b: u8, c: u8) -> Result<u8> {
+    Ok(match filter_type {
+        0 => 0,
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        4 => paeth(a, b, c),
+        other => bail!("Unknown filter type: {}", other),
+    })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+
+    #[test]
+    fn test_filter_round_trip() {
+        let mut png = Png::from_chunks(vec![
+            Chunk::new_ihdr(6, 5, 8, 2),
+            Chunk::new(ChunkType::IDAT, vec![]),
+            Chunk::new(ChunkType::IEND, vec![]),
+        ]);
+
+        let raster = Raster {
+            header: png.image_header().unwrap(),
+            pixels: (0..6 * 5 * 3).map(|i| (i * 37 % 251) as u8).collect(),
+            filter_types: vec![0, 1, 2, 3, 4],
+        };
+
+        encode(&mut png, &raster).unwrap();
+        let decoded = decode(&png).unwrap();
+
+        assert_eq!(decoded.pixels, raster.pixels);
+        assert_eq!(decoded.filter_types, raster.filter_types);
+    }
+
+    #[test]
+    fn test_decompress_stops_at_image_size() {
+        let mut png = Png::from_chunks(vec![
+            Chunk::new_ihdr(2, 2, 8, 0),
+            Chunk::new(ChunkType::IDAT, vec![]),
+            Chunk::new(ChunkType::IEND, vec![]),
+        ]);
+        compress(&mut png, &vec![0; 64 * 1024 * 1024]).unwrap();
+
+        let (_, filtered) = decompress(&png).unwrap();
+        assert_eq!(filtered, [0; 6]);
+    }
+}
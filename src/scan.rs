@@ -0,0 +1,272 @@
+use super::chunk::Chunk;
+use super::png::Png;
+use anyhow::{Result, anyhow, bail};
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// A chunk whose declared length does not agree with the bytes around it.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum LengthWarning {
+    /// The bytes at the offset are too short to hold a chunk length and type.
+    TruncatedHeader { offset: usize },
+
+    /// The declared length runs past the end of the buffer.
+    Overrun {
+        offset: usize,
+        chunk_type: [u8; 4],
+        declared: usize,
+        available: usize,
+    },
+
+    /// The CRC does not match the region covered by the declared length,
+    /// which usually means the length field was edited.
+    CrcMismatch {
+        offset: usize,
+        chunk_type: [u8; 4],
+        declared: usize,
+    },
+}
+
+impl Display for LengthWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Self::TruncatedHeader { offset } => {
+                write!(f, "Truncated chunk header at offset {}", offset)
+            }
+            Self::Overrun {
+                offset,
+                chunk_type,
+                declared,
+                available,
+            } => write!(
+                f,
+                "'{}' chunk at offset {} declares {} bytes but only {} remain",
+                String::from_utf8_lossy(chunk_type),
+                offset,
+                declared,
+                available
+            ),
+            Self::CrcMismatch {
+                offset,
+                chunk_type,
+                declared,
+            } => write!(
+                f,
+                "'{}' chunk at offset {} declares {} bytes but its CRC does not match",
+                String::from_utf8_lossy(chunk_type),
+                offset,
+                declared
+            ),
+        }
+    }
+}
+
+/// Returns the offset of the first PNG signature found in a byte stream.
+///
+/// Useful for locating a PNG embedded inside another file,
+/// such as a polyglot or a concatenated blob.
+pub fn find_png(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(Png::STANDARD_HEADER.len())
+        .position(|window| window == Png::STANDARD_HEADER)
+}
+
+/// Carves out the first embedded PNG from a byte stream,
+/// starting at its signature and ending right after its IEND chunk.
+///
+/// # Error
+/// Returns an error if no PNG signature is found or
+/// the stream ends before an IEND chunk is reached.
+pub fn carve_png(bytes: &[u8]) -> Result<&[u8]> {
+    let offset = find_png(bytes).ok_or_else(|| anyhow!("No PNG signature found!"))?;
+    let png_bytes = &bytes[offset..];
+
+    let end = iend_offset(png_bytes)?;
+
+    Ok(&png_bytes[..end])
+}
+
+/// Walks the chunks following the signature and returns
+/// the offset right after the end of the IEND chunk.
+pub(crate) fn iend_offset(bytes: &[u8]) -> Result<usize> {
+    chunk_end_offset(bytes, b"IEND")
+}
+
+/// Walks the chunks following the signature and returns the offset right after
+/// the end of the first chunk of the given type, or of the IEND chunk if it comes first.
+pub(crate) fn chunk_end_offset(bytes: &[u8], chunk_type: &[u8; 4]) -> Result<usize> {
+    let mut i = Png::STANDARD_HEADER.len();
+    loop {
+        if i + 8 > bytes.len() {
+            bail!(
+                "Reached end of data before finding {} chunk!",
+                String::from_utf8_lossy(chunk_type)
+            );
+        }
+
+        let length = u32::from_be_bytes(bytes[i..i + 4].try_into()?) as usize;
+        let chunk_end = i + 12 + length;
+
+        if chunk_end > bytes.len() {
+            bail!("Chunk length exceeds remaining bytes!");
+        }
+
+        if &bytes[i + 4..i + 8] == chunk_type || &bytes[i + 4..i + 8] == b"IEND" {
+            return Ok(chunk_end);
+        }
+
+        i = chunk_end;
+    }
+}
+
+/// Walks the chunks of a PNG without failing, collecting every chunk
+/// whose declared length disagrees with the surrounding bytes.
+///
+/// The walk stops at the IEND chunk or once a length overruns the buffer.
+pub(crate) fn length_warnings(bytes: &[u8]) -> Vec<LengthWarning> {
+    let mut warnings = Vec::<LengthWarning>::new();
+
+    let mut i = match bytes.starts_with(&Png::STANDARD_HEADER) {
+        true => Png::STANDARD_HEADER.len(),
+        false => 0,
+    };
+
+    while i < bytes.len() {
+        if i + 8 > bytes.len() {
+            warnings.push(LengthWarning::TruncatedHeader { offset: i });
+            break;
+        }
+
+        let declared = u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let declared = declared as usize;
+        let chunk_type = [bytes[i + 4], bytes[i + 5], bytes[i + 6], bytes[i + 7]];
+        let chunk_end = i.saturating_add(12).saturating_add(declared);
+
+        if chunk_end > bytes.len() {
+            warnings.push(LengthWarning::Overrun {
+                offset: i,
+                chunk_type,
+                declared,
+                available: bytes.len() - i - 8,
+            });
+            break;
+        }
+
+        let crc_matches = Chunk::from_bytes_unverified(&bytes[i..chunk_end])
+            .is_ok_and(|chunk| chunk.has_valid_crc());
+        if !crc_matches {
+            warnings.push(LengthWarning::CrcMismatch {
+                offset: i,
+                chunk_type,
+                declared,
+            });
+        }
+
+        if &chunk_type == b"IEND" {
+            break;
+        }
+
+        i = chunk_end;
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_png_bytes() -> Vec<u8> {
+        let chunks = vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"secret".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ];
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_find_png() {
+        let png_bytes = testing_png_bytes();
+        let bytes: Vec<u8> = [0xAB; 100].iter().chain(&png_bytes).copied().collect();
+
+        assert_eq!(find_png(&bytes), Some(100));
+    }
+
+    #[test]
+    fn test_find_png_missing() {
+        assert_eq!(find_png(&[0xAB; 100]), None);
+    }
+
+    #[test]
+    fn test_carve_png() {
+        let png_bytes = testing_png_bytes();
+        let bytes: Vec<u8> = [0xAB; 100]
+            .iter()
+            .chain(&png_bytes)
+            .chain(&[0xCD; 20])
+            .copied()
+            .collect();
+
+        let carved = carve_png(&bytes).unwrap();
+        assert_eq!(carved, png_bytes.as_slice());
+
+        let png = Png::try_from(carved).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_carve_png_without_iend() {
+        let mut png_bytes = testing_png_bytes();
+        png_bytes.truncate(png_bytes.len() - 12);
+
+        assert!(carve_png(&png_bytes).is_err());
+    }
+
+    #[test]
+    fn test_length_warnings_consistent() {
+        assert!(length_warnings(&testing_png_bytes()).is_empty());
+    }
+
+    #[test]
+    fn test_length_warnings_bad_length() {
+        let mut png_bytes = testing_png_bytes();
+        // The RuSt chunk starts after the signature and the 25 byte IHDR chunk.
+        let offset = 8 + 25;
+        png_bytes[offset..offset + 4].copy_from_slice(&4u32.to_be_bytes());
+
+        let warnings = length_warnings(&png_bytes);
+        assert_eq!(
+            warnings[0],
+            LengthWarning::CrcMismatch {
+                offset,
+                chunk_type: *b"RuSt",
+                declared: 4,
+            }
+        );
+        assert!(matches!(
+            warnings.last(),
+            Some(LengthWarning::Overrun { .. } | LengthWarning::TruncatedHeader { .. })
+        ));
+    }
+
+    #[test]
+    fn test_length_warnings_overrun() {
+        let mut png_bytes = testing_png_bytes();
+        let offset = 8 + 25;
+        png_bytes[offset..offset + 4].copy_from_slice(&1000u32.to_be_bytes());
+
+        assert_eq!(
+            length_warnings(&png_bytes),
+            vec![LengthWarning::Overrun {
+                offset,
+                chunk_type: *b"RuSt",
+                declared: 1000,
+                available: png_bytes.len() - offset - 8,
+            }]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// The [SizeBreakdown] struct splits the serialized size of a PNG
+/// into the bytes used by each kind of content.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct SizeBreakdown {
+    /// Data bytes of critical chunks.
+    pub critical: usize,
+
+    /// Data bytes of ancillary chunks.
+    pub ancillary: usize,
+
+    /// Signature plus the length, type and CRC fields of every chunk.
+    pub overhead: usize,
+
+    /// Bytes following the IEND chunk.
+    pub trailer: usize,
+}
+
+impl SizeBreakdown {
+    /// Returns the total serialized size of the PNG.
+    pub fn total(&self) -> usize {
+        self.critical + self.ancillary + self.overhead + self.trailer
+    }
+}
+
+impl Display for SizeBreakdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        writeln!(f, "Critical: {} bytes", self.critical)?;
+        writeln!(f, "Ancillary: {} bytes", self.ancillary)?;
+        writeln!(f, "Overhead: {} bytes", self.overhead)?;
+        writeln!(f, "Trailer: {} bytes", self.trailer)?;
+        writeln!(f, "Total: {} bytes", self.total())
+    }
+}
@@ -0,0 +1,635 @@
+use super::image_header::ImageHeader;
+use super::png::Png;
+use super::raster;
+use anyhow::{Result, bail};
+use crc::{CRC_32_ISO_HDLC, Crc};
+
+/// Number of bytes used to store the payload length in front of the payload.
+pub(crate) const LENGTH_HEADER_SIZE: usize = 4;
+
+/// Number of bytes used to store the checksum of each striped copy.
+const CHECKSUM_SIZE: usize = 4;
+
+const CHECKSUM: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// The offset of a byte holding a hidden bit, along with the position of that bit.
+type Carrier = (usize, u8);
+
+/// The unit of pixel data that each hidden bit is stored in.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum LsbGranularity {
+    /// Stores a bit in the low bit of every sample.
+    /// For 16-bit images, this is the low bit of each two byte sample.
+    #[default]
+    Sample,
+
+    /// Stores a bit in the low bit of every byte of pixel data.
+    /// For 16-bit images, this doubles the capacity but also flips
+    /// the low bit of each sample's high byte, which is visibly lossy.
+    Byte,
+}
+
+/// The stage of PNG encoding at which hidden bits are written.
+///
+/// Either way, the filter-type byte leading each scanline is never touched.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum LsbStage {
+    /// Writes bits into the unfiltered samples, then re-applies each
+    /// scanline's original filter. Decoded pixels only ever change
+    /// in their least significant bits.
+    #[default]
+    PreFilter,
+
+    /// Writes bits straight into the filtered scanline bytes. For scanlines
+    /// using any filter other than None, each flipped bit propagates through
+    /// the filter's prediction into neighbouring pixels, producing visible artifacts.
+    PostFilter,
+}
+
+/// How the payload is laid out across the scanlines of the image.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum LsbLayout {
+    /// Writes a single copy of the payload starting from the top of the image.
+    #[default]
+    Sequential,
+
+    /// Splits the image height into bands and writes a checksummed copy
+    /// of the payload into each one, so a copy survives cropping or damage
+    /// to part of the image. Capacity shrinks accordingly.
+    Striped { copies: usize },
+}
+
+/// Options controlling how data is hidden in the pixel data.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct LsbOptions {
+    /// The unit of pixel data that each hidden bit is stored in.
+    pub granularity: LsbGranularity,
+
+    /// The stage of PNG encoding at which hidden bits are written.
+    pub stage: LsbStage,
+
+    /// How the payload is laid out across the scanlines of the image.
+    pub layout: LsbLayout,
+
+    /// How many low bits of each carrier hold hidden data, from 1 to 8.
+    /// Every extra bit adds the same capacity again, but changes pixels more visibly.
+    pub bits_per_channel: u8,
+}
+
+impl Default for LsbOptions {
+    fn default() -> Self {
+        Self {
+            granularity: LsbGranularity::default(),
+            stage: LsbStage::default(),
+            layout: LsbLayout::default(),
+            bits_per_channel: 1,
+        }
+    }
+}
+
+/// Returns the number of payload bytes that can be hidden in
+/// the pixel data of the PNG using the default options.
+///
+/// For 8-bit images this is [Png::capacity_bytes] minus the length header
+/// written in front of the payload, and it is the 1 bit LSB capacity
+/// reported by [Png::capacity_report].
+pub fn lsb_capacity(png: &Png) -> Result<usize> {
+    lsb_capacity_with(png, &LsbOptions::default())
+}
+
+/// Returns the number of payload bytes that can be hidden in
+/// the pixel data of the PNG using the given options.
+///
+/// # Error
+/// Returns an error if the IHDR chunk is missing or the image
+/// format is not supported by the LSB embedder.
+pub fn lsb_capacity_with(png: &Png, options: &LsbOptions) -> Result<usize> {
+    let header = png.image_header()?;
+    let carriers = lsb_carrier_count(png, options)?;
+
+    match options.layout {
+        LsbLayout::Sequential => Ok((carriers / 8).saturating_sub(LENGTH_HEADER_SIZE)),
+        LsbLayout::Striped { copies } => {
+            let height = header.height as usize;
+            check_copies(copies, height)?;
+
+            let band = (height / copies) * (carriers / height);
+            Ok((band / 8).saturating_sub(LENGTH_HEADER_SIZE + CHECKSUM_SIZE))
+        }
+    }
+}
+
+/// Returns the number of hidden bits the pixel data is able to carry.
+pub(crate) fn lsb_carrier_count(png: &Png, options: &LsbOptions) -> Result<usize> {
+    let header = png.image_header()?;
+    check_supported(&header)?;
+
+    Ok(header.height as usize * carriers_per_row(&header, options)?)
+}
+
+/// Hides the payload in the least significant bits of the pixel data
+/// using the default options.
+pub fn embed_lsb(png: &mut Png, payload: &[u8]) -> Result<()> {
+    embed_lsb_with(png, payload, &LsbOptions::default())
+}
+
+/// Hides the payload in the least significant bits of the pixel data.
+///
+/// The IDAT stream is decompressed, the payload (prefixed with its length)
+/// is written bit by bit, and the result is recompressed into a single IDAT chunk.
+///
+/// # Error
+/// Returns an error if the image format is not supported or
+/// the payload does not fit in the available capacity.
+pub fn embed_lsb_with(png: &mut Png, payload: &[u8], options: &LsbOptions) -> Result<()> {
+    match options.stage {
+        LsbStage::PreFilter => {
+            let mut raster = raster::decode(png)?;
+            check_supported(&raster.header)?;
+
+            let carriers = carrier_offsets(&raster.header, options, 0)?;
+            write_layout(
+                &mut raster.pixels,
+                &carriers,
+                &raster.header,
+                options,
+                payload,
+            )?;
+
+            raster::encode(png, &raster)
+        }
+        LsbStage::PostFilter => {
+            let (header, mut filtered) = raster::decompress(png)?;
+            check_supported(&header)?;
+
+            let carriers = carrier_offsets(&header, options, 1)?;
+            write_layout(&mut filtered, &carriers, &header, options, payload)?;
+
+            raster::compress(png, &filtered)
+        }
+    }
+}
+
+/// Recovers a payload hidden in the pixel data using the default options.
+pub fn extract_lsb(png: &Png) -> Result<Vec<u8>> {
+    extract_lsb_with(png, &LsbOptions::default())
+}
+
+/// Recovers a payload hidden in the pixel data.
+/// The options must match the ones used for embedding.
+///
+/// # Error
+/// Returns an error if the image format is not supported or
+/// no valid payload length is found.
+pub fn extract_lsb_with(png: &Png, options: &LsbOptions) -> Result<Vec<u8>> {
+    let (header, bytes, leading) = match options.stage {
+        LsbStage::PreFilter => {
+            let raster = raster::decode(png)?;
+            (raster.header, raster.pixels, 0)
+        }
+        LsbStage::PostFilter => {
+            let (header, filtered) = raster::decompress(png)?;
+            (header, filtered, 1)
+        }
+    };
+    check_supported(&header)?;
+
+    let carriers = carrier_offsets(&header, options, leading)?;
+    match options.layout {
+        LsbLayout::Sequential => read_bits(&bytes, &carriers),
+        LsbLayout::Striped { copies } => {
+            for band in bands(&carriers, header.height as usize, copies)? {
+                let Ok(copy) = read_bits(&bytes, band) else {
+                    continue;
+                };
+
+                if copy.len() >= CHECKSUM_SIZE {
+                    let (checksum, payload) = copy.split_at(CHECKSUM_SIZE);
+                    if checksum == CHECKSUM.checksum(payload).to_be_bytes() {
+                        return Ok(payload.to_vec());
+                    }
+                }
+            }
+
+            bail!("No intact copy of the LSB payload found.");
+        }
+    }
+}
+
+/// Writes the payload into the carriers following the layout of the options.
+fn write_layout(
+    bytes: &mut [u8],
+    carriers: &[Carrier],
+    header: &ImageHeader,
+    options: &LsbOptions,
+    payload: &[u8],
+) -> Result<()> {
+    match options.layout {
+        LsbLayout::Sequential => write_bits(bytes, carriers, payload),
+        LsbLayout::Striped { copies } => {
+            let mut copy = CHECKSUM.checksum(payload).to_be_bytes().to_vec();
+            copy.extend_from_slice(payload);
+
+            for band in bands(carriers, header.height as usize, copies)? {
+                write_bits(bytes, band, &copy)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Splits row-major carriers into bands of whole scanlines spread across the image height.
+fn bands(carriers: &[Carrier], height: usize, copies: usize) -> Result<Vec<&[Carrier]>> {
+    check_copies(copies, height)?;
+
+    let per_row = carriers.len() / height;
+    Ok((0..copies)
+        .map(|i| &carriers[i * height / copies * per_row..(i + 1) * height / copies * per_row])
+        .collect())
+}
+
+fn check_copies(copies: usize, height: usize) -> Result<()> {
+    if copies == 0 || copies > height {
+        bail!(
+            "Striped layout needs between 1 and {} copies, found {}.",
+            height,
+            copies
+        );
+    }
+    Ok(())
+}
+
+fn write_bits(bytes: &mut [u8], carriers: &[Carrier], payload: &[u8]) -> Result<()> {
+    let capacity = (carriers.len() / 8).saturating_sub(LENGTH_HEADER_SIZE);
+    if payload.len() > capacity {
+        bail!(
+            "Payload of {} bytes exceeds the LSB capacity of {} bytes.",
+            payload.len(),
+            capacity
+        );
+    }
+
+    let length = (payload.len() as u32).to_be_bytes();
+    let bits = length
+        .iter()
+        .chain(payload)
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1));
+
+    carriers
+        .iter()
+        .zip(bits)
+        .for_each(|(&(offset, shift), bit)| {
+            bytes[offset] = (bytes[offset] & !(1 << shift)) | (bit << shift);
+        });
+
+    Ok(())
+}
+
+fn read_bits(bytes: &[u8], carriers: &[Carrier]) -> Result<Vec<u8>> {
+    let capacity = (carriers.len() / 8).saturating_sub(LENGTH_HEADER_SIZE);
+
+    let mut payload = carriers
+        .chunks_exact(8)
+        .map(|offsets| {
+            offsets.iter().fold(0u8, |byte, &(offset, shift)| {
+                (byte << 1) | ((bytes[offset] >> shift) & 1)
+            })
+        })
+        .collect::<Vec<u8>>();
+
+    if payload.len() < LENGTH_HEADER_SIZE {
+        bail!("Image is too small to hold an LSB payload.");
+    }
+
+    let length = u32::from_be_bytes(payload[..LENGTH_HEADER_SIZE].try_into()?) as usize;
+    if length > capacity {
+        bail!("No valid LSB payload found.");
+    }
+
+    payload.truncate(LENGTH_HEADER_SIZE + length);
+    Ok(payload.split_off(LENGTH_HEADER_SIZE))
+}
+
+fn check_supported(header: &ImageHeader) -> Result<()> {
+    if header.color_type == 3 {
+        bail!("Palette images are not supported for LSB embedding.");
+    }
+
+    if header.bit_depth != 8 && header.bit_depth != 16 {
+        bail!(
+            "Bit depth of {} is not supported for LSB embedding.",
+            header.bit_depth
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the offset of the first carrier byte within a scanline,
+/// along with the distance between consecutive carriers.
+/// For 16-bit samples stored in big-endian order, the low bit lives
+/// in the second byte of each sample.
+fn carrier_step(header: &ImageHeader, options: &LsbOptions) -> (usize, usize) {
+    match (options.granularity, header.bytes_per_sample()) {
+        (LsbGranularity::Sample, 2) => (1, 2),
+        _ => (0, 1),
+    }
+}
+
+fn check_bits_per_channel(options: &LsbOptions) -> Result<()> {
+    if !(1..=8).contains(&options.bits_per_channel) {
+        bail!(
+            "LSB embedding needs between 1 and 8 bits per channel, found {}.",
+            options.bits_per_channel
+        );
+    }
+    Ok(())
+}
+
+fn carriers_per_row(header: &ImageHeader, options: &LsbOptions) -> Result<usize> {
+    check_bits_per_channel(options)?;
+
+    let (start, step) = carrier_step(header, options);
+    Ok((start..header.scanline_len()?).step_by(step).len() * options.bits_per_channel as usize)
+}
+
+/// Returns the offset and bit position of every hidden bit,
+/// where every scanline is preceded by `leading` bytes that must be skipped.
+fn carrier_offsets(
+    header: &ImageHeader,
+    options: &LsbOptions,
+    leading: usize,
+) -> Result<Vec<Carrier>> {
+    check_bits_per_channel(options)?;
+
+    let (start, step) = carrier_step(header, options);
+    let stride = header.scanline_len()?;
+    let bits = options.bits_per_channel;
+
+    Ok((0..header.height as usize)
+        .flat_map(|row| {
+            let row_start = row * (stride + leading) + leading;
+            (start..stride)
+                .step_by(step)
+                .flat_map(move |i| (0..bits).map(move |shift| (row_start + i, shift)))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+    use std::str::FromStr;
+
+    fn testing_png(width: u32, height: u32, bit_depth: u8, color_type: u8, pixels: &[u8]) -> Png {
+        let ihdr = Chunk::new_ihdr(width, height, bit_depth, color_type);
+        let header = ImageHeader::try_from(&ihdr).unwrap();
+        let stride = header.scanline_len().unwrap();
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in pixels.chunks(stride) {
+            encoder.write_all(&[0]).unwrap();
+            encoder.write_all(row).unwrap();
+        }
+
+        Png::from_chunks(vec![
+            ihdr,
+            Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                encoder.finish().unwrap(),
+            ),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    fn grayscale_16_bit_png() -> Png {
+        let pixels: Vec<u8> = (0..16 * 16)
+            .flat_map(|i: u16| (i * 257).to_be_bytes())
+            .collect();
+        testing_png(16, 16, 16, 0, &pixels)
+    }
+
+    #[test]
+    fn test_lsb_capacity_16_bit() {
+        let png = grayscale_16_bit_png();
+        let byte_options = LsbOptions {
+            granularity: LsbGranularity::Byte,
+            ..Default::default()
+        };
+
+        assert_eq!(lsb_capacity(&png).unwrap(), 16 * 16 / 8 - 4);
+        assert_eq!(
+            lsb_capacity_with(&png, &byte_options).unwrap(),
+            16 * 16 * 2 / 8 - 4
+        );
+    }
+
+    #[test]
+    fn test_lsb_round_trip_16_bit_grayscale() {
+        let mut png = grayscale_16_bit_png();
+        let original = raster::decode(&png).unwrap().pixels;
+
+        embed_lsb(&mut png, b"16 bit secret").unwrap();
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(extract_lsb(&png).unwrap(), b"16 bit secret");
+
+        let modified = raster::decode(&png).unwrap().pixels;
+        original
+            .chunks(2)
+            .zip(modified.chunks(2))
+            .for_each(|(before, after)| {
+                assert_eq!(before[0], after[0]);
+                assert_eq!(before[1] & !1, after[1] & !1);
+            });
+    }
+
+    #[test]
+    fn test_lsb_round_trip_16_bit_byte_granularity() {
+        let mut png = grayscale_16_bit_png();
+        let options = LsbOptions {
+            granularity: LsbGranularity::Byte,
+            ..Default::default()
+        };
+        let payload = [0xA5; 60];
+
+        embed_lsb_with(&mut png, &payload, &options).unwrap();
+        assert_eq!(extract_lsb_with(&png, &options).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_lsb_round_trip_rgba() {
+        let pixels: Vec<u8> = (0..8 * 8 * 4).map(|i| i as u8).collect();
+        let mut png = testing_png(8, 8, 8, 6, &pixels);
+
+        embed_lsb(&mut png, b"RGBA secret").unwrap();
+        assert_eq!(extract_lsb(&png).unwrap(), b"RGBA secret");
+    }
+
+    #[test]
+    fn test_lsb_round_trip_real_rgba_png() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.color_type().unwrap(), 6);
+        // 50x50 RGBA pixels carry one bit per byte, minus the length header.
+        assert_eq!(lsb_capacity(&png).unwrap(), 50 * 50 * 4 / 8 - 4);
+
+        let payload: Vec<u8> = (0..1000).map(|i| (i * 7) as u8).collect();
+        embed_lsb(&mut png, &payload).unwrap();
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(extract_lsb(&png).unwrap(), payload);
+        assert_eq!(png.dimensions().unwrap(), (50, 50));
+    }
+
+    #[test]
+    fn test_lsb_round_trip_multiple_bits() {
+        let pixels: Vec<u8> = (0..16 * 16 * 3).map(|i| (i * 7 % 256) as u8).collect();
+
+        for bits_per_channel in [2, 3] {
+            let mut png = testing_png(16, 16, 8, 2, &pixels);
+            let options = LsbOptions {
+                bits_per_channel,
+                ..Default::default()
+            };
+
+            let capacity = lsb_capacity_with(&png, &options).unwrap();
+            assert_eq!(capacity, 16 * 16 * 3 * bits_per_channel as usize / 8 - 4);
+            assert_eq!(
+                png.capacity_report()
+                    .unwrap()
+                    .lsb_bits(bits_per_channel as usize),
+                Some(capacity)
+            );
+            assert!(embed_lsb_with(&mut png.clone(), &vec![0; capacity + 1], &options).is_err());
+
+            let payload: Vec<u8> = (0..capacity).map(|i| (i * 13) as u8).collect();
+            embed_lsb_with(&mut png, &payload, &options).unwrap();
+            assert_eq!(extract_lsb_with(&png, &options).unwrap(), payload);
+
+            let mask = !((1u8 << bits_per_channel) - 1);
+            raster::decode(&png)
+                .unwrap()
+                .pixels
+                .iter()
+                .zip(&pixels)
+                .for_each(|(after, before)| assert_eq!(after & mask, before & mask));
+        }
+
+        let options = LsbOptions {
+            bits_per_channel: 0,
+            ..Default::default()
+        };
+        assert!(lsb_capacity_with(&testing_png(1, 1, 8, 0, &[0]), &options).is_err());
+    }
+
+    fn filtered_rgb_png() -> Png {
+        let mut png = testing_png(16, 5, 8, 2, &[0; 16 * 5 * 3]);
+        let raster = raster::Raster {
+            header: png.image_header().unwrap(),
+            pixels: (0..16 * 5 * 3).map(|i| (i * 37 % 251) as u8).collect(),
+            filter_types: vec![0, 1, 2, 3, 4],
+        };
+        raster::encode(&mut png, &raster).unwrap();
+        png
+    }
+
+    #[test]
+    fn test_lsb_filtered_image_changes_only_lsbs() {
+        let mut png = filtered_rgb_png();
+        let original = raster::decode(&png).unwrap();
+
+        embed_lsb(&mut png, b"filtered secret").unwrap();
+        assert_eq!(extract_lsb(&png).unwrap(), b"filtered secret");
+
+        let modified = raster::decode(&png).unwrap();
+        assert_eq!(modified.filter_types, original.filter_types);
+        assert!(original.pixels != modified.pixels);
+        original
+            .pixels
+            .iter()
+            .zip(&modified.pixels)
+            .for_each(|(before, after)| assert_eq!(before & !1, after & !1));
+    }
+
+    #[test]
+    fn test_lsb_post_filter_round_trip() {
+        let mut png = filtered_rgb_png();
+        let options = LsbOptions {
+            stage: LsbStage::PostFilter,
+            ..Default::default()
+        };
+
+        embed_lsb_with(&mut png, b"filtered secret", &options).unwrap();
+        assert_eq!(
+            extract_lsb_with(&png, &options).unwrap(),
+            b"filtered secret"
+        );
+
+        let (_, filtered) = raster::decompress(&png).unwrap();
+        let filter_types: Vec<u8> = filtered.chunks(16 * 3 + 1).map(|row| row[0]).collect();
+        assert_eq!(filter_types, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_lsb_payload_too_large() {
+        let mut png = grayscale_16_bit_png();
+        assert!(embed_lsb(&mut png, &[0; 29]).is_err());
+    }
+
+    #[test]
+    fn test_lsb_unsupported_palette() {
+        let mut png = testing_png(4, 4, 8, 3, &[0; 16]);
+        assert!(embed_lsb(&mut png, b"secret").is_err());
+    }
+
+    #[test]
+    fn test_lsb_striped_survives_damage() {
+        let pixels: Vec<u8> = (0..16 * 16 * 3).map(|i| (i * 7 % 256) as u8).collect();
+        let options = LsbOptions {
+            layout: LsbLayout::Striped { copies: 2 },
+            ..Default::default()
+        };
+
+        let mut png = testing_png(16, 16, 8, 2, &pixels);
+        assert_eq!(
+            lsb_capacity_with(&png, &options).unwrap(),
+            8 * 16 * 3 / 8 - 8
+        );
+        embed_lsb_with(&mut png, b"striped secret", &options).unwrap();
+
+        let half = 16 * 8 * 3;
+        for damaged in [half..2 * half, 0..half] {
+            let mut raster = raster::decode(&png).unwrap();
+            raster.pixels[damaged].fill(0);
+
+            let mut damaged_png = png.clone();
+            raster::encode(&mut damaged_png, &raster).unwrap();
+            assert_eq!(
+                extract_lsb_with(&damaged_png, &options).unwrap(),
+                b"striped secret"
+            );
+        }
+
+        let mut raster = raster::decode(&png).unwrap();
+        raster.pixels.fill(0);
+        raster::encode(&mut png, &raster).unwrap();
+        assert!(extract_lsb_with(&png, &options).is_err());
+    }
+
+    #[test]
+    fn test_lsb_striped_invalid_copies() {
+        let mut png = testing_png(4, 4, 8, 0, &[0; 16]);
+        let options = LsbOptions {
+            layout: LsbLayout::Striped { copies: 5 },
+            ..Default::default()
+        };
+
+        assert!(embed_lsb_with(&mut png, b"", &options).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{Result, bail};
+
+/// The [TimeFields] struct represents the last-modification
+/// time stored in a tIME chunk, always in UTC.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TimeFields {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl TimeFields {
+    /// Checks that every field is within the range allowed by the PNG specification.
+    /// A second of 60 is allowed to account for leap seconds.
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=12).contains(&self.month) {
+            bail!("Invalid month: {}. Must be between 1 and 12.", self.month);
+        }
+        if !(1..=31).contains(&self.day) {
+            bail!("Invalid day: {}. Must be between 1 and 31.", self.day);
+        }
+        if self.hour > 23 {
+            bail!("Invalid hour: {}. Must be between 0 and 23.", self.hour);
+        }
+        if self.minute > 59 {
+            bail!("Invalid minute: {}. Must be between 0 and 59.", self.minute);
+        }
+        if self.second > 60 {
+            bail!("Invalid second: {}. Must be between 0 and 60.", self.second);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<TimeFields> for chrono::NaiveDateTime {
+    type Error = anyhow::Error;
+
+    fn try_from(time: TimeFields) -> Result<Self, Self::Error> {
+        use anyhow::anyhow;
+        use chrono::{NaiveDate, NaiveTime};
+
+        let date = NaiveDate::from_ymd_opt(time.year.into(), time.month.into(), time.day.into())
+            .ok_or_else(|| anyhow!("Invalid date: {:?}", time))?;
+
+        // chrono represents leap seconds as an overflowing fraction of the 59th second.
+        let clock = match time.second {
+            60 => NaiveTime::from_hms_nano_opt(
+                time.hour.into(),
+                time.minute.into(),
+                59,
+                1_000_000_000,
+            ),
+            second => NaiveTime::from_hms_opt(time.hour.into(), time.minute.into(), second.into()),
+        }
+        .ok_or_else(|| anyhow!("Invalid time: {:?}", time))?;
+
+        Ok(date.and_time(clock))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDateTime> for TimeFields {
+    type Error = anyhow::Error;
+
+    fn try_from(date_time: chrono::NaiveDateTime) -> Result<Self, Self::Error> {
+        use chrono::{Datelike, Timelike};
+
+        let year = match u16::try_from(date_time.year()) {
+            Ok(year) => year,
+            Err(_) => bail!("Year {} does not fit in a tIME chunk.", date_time.year()),
+        };
+
+        let second = match date_time.nanosecond() >= 1_000_000_000 {
+            true => 60,
+            false => date_time.second() as u8,
+        };
+
+        Ok(Self {
+            year,
+            month: date_time.month() as u8,
+            day: date_time.day() as u8,
+            hour: date_time.hour() as u8,
+            minute: date_time.minute() as u8,
+            second,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_time() -> TimeFields {
+        TimeFields {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 13,
+            minute: 37,
+            second: 42,
+        }
+    }
+
+    #[test]
+    fn test_valid_time_fields() {
+        assert!(testing_time().validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_time_fields() {
+        let time = testing_time();
+
+        assert!(TimeFields { month: 13, ..time }.validate().is_err());
+        assert!(TimeFields { day: 0, ..time }.validate().is_err());
+        assert!(TimeFields { hour: 24, ..time }.validate().is_err());
+        assert!(TimeFields { minute: 60, ..time }.validate().is_err());
+        assert!(TimeFields { second: 61, ..time }.validate().is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_round_trip() {
+        use chrono::NaiveDateTime;
+
+        let date_time = NaiveDateTime::try_from(testing_time()).unwrap();
+        assert_eq!(date_time.to_string(), "2024-02-29 13:37:42");
+
+        let time = TimeFields::try_from(date_time).unwrap();
+        assert_eq!(time, testing_time());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_invalid_date() {
+        let time = TimeFields {
+            year: 2023,
+            ..testing_time()
+        };
+        assert!(chrono::NaiveDateTime::try_from(time).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use super::chunk_type::ChunkType;
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// A single problem found while validating a PNG,
+/// or a warning found by [Png::lint](crate::Png::lint).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Issue {
+    /// The first chunk, right after the signature, is not IHDR.
+    HeaderNotFirst { chunk_type: Option<ChunkType> },
+
+    /// An IHDR chunk appears again at the given index.
+    DuplicateHeader { index: usize },
+
+    /// The last chunk is not IEND.
+    EndNotLast { chunk_type: Option<ChunkType> },
+
+    /// An IEND chunk appears before the last chunk, at the given index.
+    DuplicateEnd { index: usize },
+
+    /// The chunk at the given index has a type that is not made of four ASCII letters.
+    InvalidChunkType { index: usize, chunk_type: ChunkType },
+
+    /// There is no IDAT chunk holding the image data.
+    MissingImageData,
+
+    /// The IHDR chunk is missing or malformed.
+    InvalidHeader { reason: String },
+
+    /// The color type requires a PLTE chunk, but none is present.
+    MissingPalette { color_type: u8 },
+
+    /// The color type forbids a PLTE chunk, but one is present.
+    UnexpectedPalette { color_type: u8 },
+
+    /// A chunk that must precede the image data appears after the first IDAT chunk.
+    AfterIdat { index: usize, chunk_type: ChunkType },
+
+    /// A tRNS chunk appears before the PLTE chunk it applies to.
+    TransparencyBeforePalette { index: usize },
+
+    /// A chunk at the given index breaks up the run of consecutive IDAT chunks.
+    InterleavedIdat { index: usize, chunk_type: ChunkType },
+
+    /// The IDAT chunk at the given index holds no data, which some decoders reject.
+    /// This is only a warning, reported by [Png::lint](crate::Png::lint).
+    EmptyIdat { index: usize },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Self::HeaderNotFirst { chunk_type: None } => {
+                write!(f, "The first chunk must be IHDR, but there are no chunks")
+            }
+            Self::HeaderNotFirst {
+                chunk_type: Some(chunk_type),
+            } => write!(f, "The first chunk must be IHDR, found '{}'", chunk_type),
+            Self::DuplicateHeader { index } => {
+                write!(f, "Duplicate IHDR chunk at index {}", index)
+            }
+            Self::EndNotLast { chunk_type: None } => {
+                write!(f, "The last chunk must be IEND, but there are no chunks")
+            }
+            Self::EndNotLast {
+                chunk_type: Some(chunk_type),
+            } => write!(f, "The last chunk must be IEND, found '{}'", chunk_type),
+            Self::DuplicateEnd { index } => {
+                write!(f, "IEND chunk at index {} must only appear last", index)
+            }
+            Self::InvalidChunkType { index, chunk_type } => write!(
+                f,
+                "Chunk type {:?} at index {} is not made of 4 ASCII letters",
+                String::from_utf8_lossy(&chunk_type.bytes()),
+                index
+            ),
+            Self::MissingImageData => write!(f, "At least one IDAT chunk is required"),
+            Self::InvalidHeader { reason } => write!(f, "Invalid IHDR chunk: {}", reason),
+            Self::MissingPalette { color_type } => {
+                write!(f, "Color type {} requires a PLTE chunk", color_type)
+            }
+            Self::UnexpectedPalette { color_type } => {
+                write!(f, "Color type {} must not have a PLTE chunk", color_type)
+            }
+            Self::AfterIdat { index, chunk_type } => write!(
+                f,
+                "'{}' chunk at index {} must appear before the first IDAT chunk",
+                chunk_type, index
+            ),
+            Self::TransparencyBeforePalette { index } => write!(
+                f,
+                "tRNS chunk at index {} must appear after the PLTE chunk",
+                index
+            ),
+            Self::InterleavedIdat { index, chunk_type } => write!(
+                f,
+                "'{}' chunk at index {} interrupts the IDAT chunks",
+                chunk_type, index
+            ),
+            Self::EmptyIdat { index } => write!(
+                f,
+                "IDAT chunk at index {} is empty, which some decoders reject",
+                index
+            ),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use pngyou::{ChunkProperties, ChunkType};
+use std::str::FromStr;
+
+#[test]
+fn test_predicates_are_public() {
+    let chunk_type = ChunkType::from_str("RuSt").unwrap();
+
+    assert_eq!(&chunk_type.bytes(), b"RuSt");
+    assert!(chunk_type.is_valid());
+    assert!(chunk_type.is_critical());
+    assert!(!chunk_type.is_public());
+    assert!(chunk_type.is_reserved_bit_valid());
+    assert!(chunk_type.is_safe_to_copy());
+}
+
+#[test]
+fn test_predicates_on_standard_chunk() {
+    let chunk_type = ChunkType::try_from(*b"tEXt").unwrap();
+
+    assert!(chunk_type.is_valid());
+    assert!(!chunk_type.is_critical());
+    assert!(chunk_type.is_public());
+    assert!(chunk_type.is_reserved_bit_valid());
+    assert!(chunk_type.is_safe_to_copy());
+    assert_eq!(chunk_type.description(), Some("textual data"));
+}
+
+#[test]
+fn test_properties() {
+    let chunk_type = ChunkType::from_str("IDAT").unwrap();
+
+    assert_eq!(
+        chunk_type.properties(),
+        ChunkProperties {
+            critical: true,
+            public: true,
+            reserved_valid: true,
+            safe_to_copy: false,
+        }
+    );
+}
@@ -0,0 +1,263 @@
+use pngyou::{Chunk, ChunkType, Png};
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn pngyou(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_pngyou"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+fn pngyou_stdout(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pngyou"))
+        .args(args)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A path in the temporary directory whose file is removed when dropped,
+/// so a failing assertion does not leave it behind.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("pngyou-cli-{}-{}", std::process::id(), name)))
+    }
+}
+
+impl Deref for TempFile {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn png_with_color_type(color_type: u8) -> Png {
+    Png::from_chunks(vec![
+        Chunk::new_ihdr(1, 1, 8, color_type),
+        Chunk::new(ChunkType::try_from(*b"IDAT").unwrap(), vec![]),
+        Chunk::new(ChunkType::try_from(*b"IEND").unwrap(), vec![]),
+    ])
+}
+
+#[test]
+fn test_exit_code_success() {
+    let input = TempFile::new("success.png");
+    fs::write(&input, png_with_color_type(2).as_bytes()).unwrap();
+
+    assert_eq!(pngyou(&["verify", input.to_str().unwrap()]), 0);
+    assert_eq!(pngyou(&["list-types"]), 0);
+}
+
+#[test]
+fn test_exit_code_validation() {
+    // Palette images require a PLTE chunk.
+    let input = TempFile::new("validation.png");
+    fs::write(&input, png_with_color_type(3).as_bytes()).unwrap();
+
+    assert_eq!(pngyou(&["verify", input.to_str().unwrap()]), 1);
+}
+
+#[test]
+fn test_exit_code_io_and_parse() {
+    let missing = TempFile::new("missing.png");
+    assert_eq!(pngyou(&["verify", missing.to_str().unwrap()]), 2);
+
+    let input = TempFile::new("not-a-png.txt");
+    fs::write(&input, b"definitely not a png file").unwrap();
+    assert_eq!(pngyou(&["verify", input.to_str().unwrap()]), 2);
+}
+
+#[test]
+fn test_exit_code_usage() {
+    assert_eq!(pngyou(&["no-such-command"]), 3);
+    assert_eq!(pngyou(&["encode", "input.png"]), 3);
+
+    let input = TempFile::new("usage.png");
+    fs::write(&input, png_with_color_type(2).as_bytes()).unwrap();
+    assert_eq!(pngyou(&["strip", input.to_str().unwrap()]), 3);
+
+    let output = TempFile::new("usage-output.png");
+    let args = [
+        "strip",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+    ];
+    assert_eq!(pngyou(&[&args[..], &["--mode", "17777"]].concat()), 3);
+    assert!(!output.exists());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_print_json() {
+    let input = TempFile::new("print.png");
+    let mut png = png_with_color_type(2);
+    png.append_chunk(Chunk::new(
+        ChunkType::try_from(*b"RuSt").unwrap(),
+        b"hey".to_vec(),
+    ));
+    fs::write(&input, png.as_bytes()).unwrap();
+
+    let output = pngyou_stdout(&["print", "--json", input.to_str().unwrap()]);
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(json["width"], 1);
+    assert_eq!(json["height"], 1);
+
+    let chunks = json["chunks"].as_array().unwrap();
+    let types: Vec<&str> = chunks
+        .iter()
+        .map(|chunk| chunk["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["IHDR", "IDAT", "RuSt", "IEND"]);
+
+    assert_eq!(chunks[0]["length"], 13);
+    assert_eq!(chunks[0]["crc"], png.chunks()[0].crc());
+    assert_eq!(chunks[0]["critical"], true);
+    assert_eq!(chunks[0]["safe_to_copy"], false);
+    assert_eq!(chunks[2]["safe_to_copy"], true);
+}
+
+#[test]
+fn test_list() {
+    let input = TempFile::new("list.png");
+    let png = png_with_color_type(2);
+    fs::write(&input, png.as_bytes()).unwrap();
+
+    let output = pngyou_stdout(&["list", input.to_str().unwrap()]);
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert_eq!(lines.len(), png.chunk_count());
+    assert_eq!(
+        lines[0],
+        format!("0: IHDR (13 bytes) CRC: {:#010x}", png.chunks()[0].crc())
+    );
+    assert!(lines[2].starts_with("2: IEND (0 bytes)"));
+}
+
+#[test]
+fn test_encode_dry_run() {
+    let input = TempFile::new("encode-dry-run.png");
+    let output = TempFile::new("encode-dry-run-output.png");
+    let bytes = png_with_color_type(2).as_bytes();
+    fs::write(&input, &bytes).unwrap();
+
+    let plan = pngyou_stdout(&[
+        "encode",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "-c",
+        "RuSt",
+        "-m",
+        "secret message",
+        "--dry-run",
+    ]);
+
+    assert_eq!(
+        plan.trim_end(),
+        "Would append 1 chunk (RuSt, 14 bytes) before IEND; file would grow by 26 bytes."
+    );
+    assert!(!output.exists());
+    assert_eq!(fs::read(&input).unwrap(), bytes);
+}
+
+#[test]
+fn test_remove_dry_run() {
+    let input = TempFile::new("remove-dry-run.png");
+    let mut png = png_with_color_type(2);
+    png.append_chunk(Chunk::new(
+        ChunkType::try_from(*b"tEXt").unwrap(),
+        b"Comment\0hey".to_vec(),
+    ));
+    let bytes = png.as_bytes();
+    fs::write(&input, &bytes).unwrap();
+
+    let plan = pngyou_stdout(&["remove", input.to_str().unwrap(), "-c", "tEXt", "--dry-run"]);
+
+    assert_eq!(
+        plan.trim_end(),
+        "Would remove first tEXt chunk at index 2; file would shrink by 23 bytes."
+    );
+    assert_eq!(fs::read(&input).unwrap(), bytes);
+    assert_eq!(
+        pngyou(&["remove", input.to_str().unwrap(), "-c", "zzZz", "--dry-run"]),
+        2
+    );
+}
+
+#[test]
+fn test_message_after_iend() {
+    // Files written by older versions hold the message after IEND.
+    let input = TempFile::new("after-iend.png");
+    let output = TempFile::new("after-iend-output.png");
+    let message = Chunk::new(ChunkType::try_from(*b"RuSt").unwrap(), b"hey".to_vec());
+    let png = png_with_color_type(2);
+    fs::write(&input, [png.as_bytes(), message.as_bytes()].concat()).unwrap();
+
+    let decoded = pngyou_stdout(&["decode", input.to_str().unwrap(), "-c", "RuSt"]);
+    assert_eq!(decoded.trim_end(), "hey");
+
+    let args = [
+        "remove",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "-c",
+        "RuSt",
+    ];
+    assert_eq!(pngyou(&args), 0);
+    assert_eq!(fs::read(&output).unwrap(), png.as_bytes());
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_password_from_env() {
+    let input = TempFile::new("password-env.png");
+    let output = TempFile::new("password-env-output.png");
+    fs::write(&input, png_with_color_type(2).as_bytes()).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_pngyou"))
+        .args([
+            "encode",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+        ])
+        .args(["-c", "RuSt", "-m", "secret message"])
+        .env("PNGYOU_PASSWORD", "hunter2")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let decoded = Command::new(env!("CARGO_BIN_EXE_pngyou"))
+        .args(["decode", output.to_str().unwrap(), "-c", "RuSt"])
+        .env("PNGYOU_PASSWORD", "hunter2")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(decoded.stdout).unwrap().trim_end(),
+        "secret message"
+    );
+}